@@ -0,0 +1,92 @@
+// Command line flags for the renderer configuration.
+
+use anyhow::{Context, bail};
+use gfx::config::{ColorSpace, RendererConfig};
+
+pub const USAGE: &str = "\
+Usage: rendering_playground [OPTIONS]
+
+Options:
+  --backend <NAME>         vulkan, metal, dx12, gl, primary (default) or all
+  --power <PREFERENCE>     none (default), low or high
+  --fallback-adapter       only use a software adapter
+  --color-space <SPACE>    srgb (default) or linear
+  --present-mode <MODE>    auto-vsync (default), auto-no-vsync, fifo,
+                           fifo-relaxed, immediate or mailbox
+  --frame-latency <N>      frames queued ahead of the display (default 2)
+  --msaa <N>               samples per pixel: 1 (default), 2, 4 or 8
+  --depth                  add a depth buffer
+  --masks                  add a stencil buffer for masking elements
+  --opaque                 do not blend the window with the desktop
+  --shader-dir <DIR>       load shaders from DIR and reload them when saved,
+                           for example gfx/src
+  --profile                draw a graph of frame times
+  -h, --help               print this help";
+
+/// Parses the flags after the program name. Returns `None` when help was
+/// asked for.
+pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Option<RendererConfig>> {
+    let mut config = RendererConfig::default();
+    let mut args = args.into_iter();
+
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--backend" => {
+                config.backends = Some(match value()?.as_str() {
+                    "vulkan" => wgpu::Backends::VULKAN,
+                    "metal" => wgpu::Backends::METAL,
+                    "dx12" => wgpu::Backends::DX12,
+                    "gl" => wgpu::Backends::GL,
+                    "primary" => wgpu::Backends::PRIMARY,
+                    "all" => wgpu::Backends::all(),
+                    other => bail!("unknown backend {other:?}"),
+                })
+            }
+            "--power" => {
+                config.power_preference = match value()?.as_str() {
+                    "none" => wgpu::PowerPreference::None,
+                    "low" => wgpu::PowerPreference::LowPower,
+                    "high" => wgpu::PowerPreference::HighPerformance,
+                    other => bail!("unknown power preference {other:?}"),
+                }
+            }
+            "--fallback-adapter" => config.force_fallback_adapter = true,
+            "--color-space" => {
+                config.color_space = match value()?.as_str() {
+                    "srgb" => ColorSpace::Srgb,
+                    "linear" => ColorSpace::Linear,
+                    other => bail!("unknown color space {other:?}"),
+                }
+            }
+            "--present-mode" => {
+                config.present_mode = match value()?.as_str() {
+                    "auto-vsync" => wgpu::PresentMode::AutoVsync,
+                    "auto-no-vsync" => wgpu::PresentMode::AutoNoVsync,
+                    "fifo" => wgpu::PresentMode::Fifo,
+                    "fifo-relaxed" => wgpu::PresentMode::FifoRelaxed,
+                    "immediate" => wgpu::PresentMode::Immediate,
+                    "mailbox" => wgpu::PresentMode::Mailbox,
+                    other => bail!("unknown present mode {other:?}"),
+                }
+            }
+            "--frame-latency" => {
+                let value = value()?;
+                config.max_frame_latency = value.parse().with_context(|| format!("invalid frame latency {value:?}"))?;
+            }
+            "--msaa" => {
+                let value = value()?;
+                config.sample_count = value.parse().with_context(|| format!("invalid sample count {value:?}"))?;
+            }
+            "--depth" => config.depth_buffer = true,
+            "--masks" => config.masks = true,
+            "--profile" => config.profiler_overlay = true,
+            "--opaque" => config.composite_alpha = wgpu::CompositeAlphaMode::Opaque,
+            "--shader-dir" => config.shader_dir = Some(value()?.into()),
+            other => bail!("unknown option {other:?}"),
+        }
+    }
+
+    Ok(Some(config))
+}
@@ -1,4 +1,6 @@
+mod args;
 
+use gfx::ui::Element;
 use winit::
     event_loop::{ControlFlow, EventLoop}
 ;
@@ -10,6 +12,18 @@ fn main() {
     // documentation for more information.
     env_logger::init();
 
+    let config = match args::parse(std::env::args().skip(1)) {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            println!("{}", args::USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err:#}\n\n{}", args::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     let event_loop = EventLoop::new().unwrap();
 
     // When the current loop iteration finishes, immediately begin a new
@@ -24,6 +38,15 @@ fn main() {
     // the background.
     // event_loop.set_control_flow(ControlFlow::Wait);
 
-    let mut app = gfx::App::default();
+    let mut app = gfx::App::new(config);
+    app.scene_mut().add(
+        Element::new()
+            .with_color([1.0, 0.0, 0.0, 1.0])
+            .with_shape(vec![
+                [365.0, 152.0, 0.0], // A
+                [202.0, 279.0, 0.0], // B
+                [312.0, 435.0, 0.0], // C
+            ]),
+    );
     event_loop.run_app(&mut app).unwrap();
 }
\ No newline at end of file
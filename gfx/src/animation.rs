@@ -0,0 +1,658 @@
+// Animations of element properties.
+//
+// An `Animator` holds the animations running in a scene and advances them by
+// the time between frames, writing their values into the elements. Values
+// are interpolated along a progress going from 0 to 1: eased over a fixed
+// duration for tweens and keyframes, or following a spring that takes as
+// long as it needs to settle. Progress may leave [0, 1] on the way, which
+// springs and some easings use to overshoot.
+
+use std::time::Duration;
+
+use crate::{
+    layout::Size,
+    scene::{ElementId, Scene},
+    transform::Transform,
+    ui::Element,
+};
+
+/// A value that can be interpolated.
+pub trait Animatable: Copy {
+    /// The value a fraction `t` of the way from `self` to `to`. `t` may be
+    /// outside of [0, 1].
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(&self, to: &f32, t: f32) -> f32 {
+        self + (to - self) * t
+    }
+}
+
+impl<const N: usize> Animatable for [f32; N] {
+    fn lerp(&self, to: &[f32; N], t: f32) -> [f32; N] {
+        std::array::from_fn(|i| self[i].lerp(&to[i], t))
+    }
+}
+
+/// Interpolates the matrices entry by entry. That is exact for moves, scales
+/// and skews, but a rotation shrinks on the way, the more the larger its
+/// angle; keyframes a few degrees apart avoid that.
+impl Animatable for Transform {
+    fn lerp(&self, to: &Transform, t: f32) -> Transform {
+        Transform::from_matrix(self.matrix().lerp(&to.matrix(), t))
+    }
+}
+
+/// Something about an element that animations can change.
+pub struct Property<T> {
+    name: &'static str,
+    get: fn(&Element) -> T,
+    set: fn(&mut Scene, ElementId, T),
+}
+
+impl<T> Clone for Property<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Property<T> {}
+
+impl<T> Property<T> {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// The color of the element, see [`Element::with_color`]. Colors are part of
+/// the vertices, so every frame of the animation tessellates the element
+/// again, with its descendants.
+pub const COLOR: Property<[f32; 4]> = Property { name: "color", get: Element::color, set: set_color };
+
+/// See [`Element::with_opacity`]. Like [`COLOR`], it tessellates the element
+/// and its descendants on every frame, so large subtrees fade cheaper as
+/// separate elements.
+pub const OPACITY: Property<f32> = Property { name: "opacity", get: Element::opacity, set: set_opacity };
+
+/// See [`Element::with_transform`]. Only the transforms are uploaded again,
+/// without tessellating the element.
+pub const TRANSFORM: Property<Transform> = Property { name: "transform", get: Element::transform, set: set_transform };
+
+/// The width and height of the element's layout, fixing both to pixels while
+/// animated. Starts from the size of the last layout unless they already
+/// are.
+pub const SIZE: Property<[f32; 2]> = Property { name: "size", get: size, set: set_size };
+
+fn set_color(scene: &mut Scene, id: ElementId, color: [f32; 4]) {
+    if let Some(element) = scene.get_mut(id) {
+        element.set_color(color);
+    }
+}
+
+fn set_opacity(scene: &mut Scene, id: ElementId, opacity: f32) {
+    if let Some(element) = scene.get_mut(id) {
+        element.set_opacity(opacity);
+    }
+}
+
+fn set_transform(scene: &mut Scene, id: ElementId, transform: Transform) {
+    scene.set_transform(id, transform);
+}
+
+fn size(element: &Element) -> [f32; 2] {
+    let layout = element.layout();
+    let rect = element.rect();
+    let px = |size, laid_out| match size {
+        Size::Px(px) => px,
+        _ => laid_out,
+    };
+    [px(layout.width, rect.width), px(layout.height, rect.height)]
+}
+
+fn set_size(scene: &mut Scene, id: ElementId, [width, height]: [f32; 2]) {
+    if let Some(element) = scene.get_mut(id) {
+        let layout = element.layout_mut();
+        layout.width = Size::Px(width);
+        layout.height = Size::Px(height);
+    }
+}
+
+/// How progress is spread over the duration of a tween. The curves are the
+/// usual ones, see <https://easings.net>.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    /// Backs up below 0 before starting.
+    BackIn,
+    /// Overshoots past 1 before settling.
+    BackOut,
+    BackInOut,
+    /// Swings past 1 and back a few times, like a plucked string.
+    ElasticOut,
+    /// Bounces off 1 like a dropped ball.
+    BounceOut,
+    /// A CSS `cubic-bezier(x1, y1, x2, y2)` timing function. `x1` and `x2`
+    /// have to be in [0, 1].
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// CSS `ease`.
+    pub const EASE: Easing = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    /// CSS `ease-in`.
+    pub const EASE_IN: Easing = Easing::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    /// CSS `ease-out`.
+    pub const EASE_OUT: Easing = Easing::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    /// CSS `ease-in-out`.
+    pub const EASE_IN_OUT: Easing = Easing::CubicBezier(0.42, 0.0, 0.58, 1.0);
+
+    /// The progress a fraction `t` of the way through, which is clamped to
+    /// [0, 1]. Maps 0 to 0 and 1 to 1.
+    pub fn apply(&self, t: f32) -> f32 {
+        use std::f32::consts::PI;
+
+        const BACK: f32 = 1.70158;
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Easing::QuadInOut => 1.0 - (2.0 - 2.0 * t).powi(2) / 2.0,
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut if t < 0.5 => 4.0 * t.powi(3),
+            Easing::CubicInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => (1.0 - (t * PI).cos()) / 2.0,
+            Easing::ExpoIn | Easing::ExpoOut | Easing::ExpoInOut if t == 0.0 || t == 1.0 => t,
+            Easing::ExpoIn => 2f32.powf(10.0 * t - 10.0),
+            Easing::ExpoOut => 1.0 - 2f32.powf(-10.0 * t),
+            Easing::ExpoInOut if t < 0.5 => 2f32.powf(20.0 * t - 10.0) / 2.0,
+            Easing::ExpoInOut => (2.0 - 2f32.powf(10.0 - 20.0 * t)) / 2.0,
+            Easing::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Easing::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::BackInOut => {
+                let back = BACK * 1.525;
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((back + 1.0) * 2.0 * t - back) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((back + 1.0) * (2.0 * t - 2.0) + back) + 2.0) / 2.0
+                }
+            }
+            Easing::ElasticOut if t == 0.0 || t == 1.0 => t,
+            Easing::ElasticOut => 2f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0,
+            Easing::BounceOut => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    N * (t - 1.5 / D).powi(2) + 0.75
+                } else if t < 2.5 / D {
+                    N * (t - 2.25 / D).powi(2) + 0.9375
+                } else {
+                    N * (t - 2.625 / D).powi(2) + 0.984375
+                }
+            }
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+// Solves the x of the curve for its parameter, with Newton's method where it
+// converges and bisection where the slope is too flat for it.
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    // Polynomial coefficients of a curve from 0 to 1 with the given control
+    // points.
+    let coefficients = |p1: f32, p2: f32| {
+        let c = 3.0 * p1;
+        let b = 3.0 * (p2 - p1) - c;
+        [1.0 - c - b, b, c]
+    };
+    let [ax, bx, cx] = coefficients(x1, x2);
+    let [ay, by, cy] = coefficients(y1, y2);
+    let curve_x = |s: f32| ((ax * s + bx) * s + cx) * s;
+
+    let mut s = x;
+    let mut solved = false;
+    for _ in 0..8 {
+        let error = curve_x(s) - x;
+        if error.abs() < 1e-6 {
+            solved = true;
+            break;
+        }
+        let slope = (3.0 * ax * s + 2.0 * bx) * s + cx;
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+    if !solved || !(0.0..=1.0).contains(&s) {
+        let (mut low, mut high) = (0.0, 1.0);
+        s = x;
+        for _ in 0..32 {
+            if curve_x(s) < x {
+                low = s;
+            } else {
+                high = s;
+            }
+            s = (low + high) / 2.0;
+        }
+    }
+    ((ay * s + by) * s + cy) * s
+}
+
+// A spring has settled once it stays this close to its target, relative to
+// the distance it started from.
+const SPRING_SETTLED: f32 = 1e-3;
+// Springs that would take longer, such as undamped ones, stop here.
+const MAX_SPRING_TIME: f32 = 60.0;
+
+/// A damped spring pulling a value from rest towards its target. Springs
+/// take as long as they need to settle rather than a fixed duration, and
+/// overshoot when damped less than critically.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub mass: f32,
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Spring::new(170.0, 26.0)
+    }
+}
+
+impl Spring {
+    /// Slow, with a little overshoot.
+    pub const GENTLE: Spring = Spring { stiffness: 120.0, damping: 14.0, mass: 1.0 };
+    /// Bounces around the target a few times.
+    pub const WOBBLY: Spring = Spring { stiffness: 180.0, damping: 12.0, mass: 1.0 };
+    /// Fast, barely overshooting.
+    pub const STIFF: Spring = Spring { stiffness: 210.0, damping: 20.0, mass: 1.0 };
+
+    /// A spring with a mass of 1.
+    pub fn new(stiffness: f32, damping: f32) -> Spring {
+        Spring { stiffness, damping, mass: 1.0 }
+    }
+
+    /// The progress towards the target `time` after the spring is released,
+    /// starting at 0.
+    pub fn progress(&self, time: Duration) -> f32 {
+        match self.motion(time.as_secs_f32()) {
+            Some((displacement, _)) => 1.0 + displacement,
+            None => 1.0,
+        }
+    }
+
+    /// How long until the spring stays within a thousandth of the distance
+    /// to its target, at most a minute.
+    pub fn settle_time(&self) -> Duration {
+        let settled = |t| self.motion(t).is_none_or(|(_, bound)| bound <= SPRING_SETTLED);
+        let mut high = 1.0 / 64.0;
+        while !settled(high) {
+            if high >= MAX_SPRING_TIME {
+                return Duration::from_secs_f32(MAX_SPRING_TIME);
+            }
+            high *= 2.0;
+        }
+        // The bound only ever decreases.
+        let mut low = 0.0;
+        for _ in 0..24 {
+            let middle = (low + high) / 2.0;
+            if settled(middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Duration::from_secs_f32(high)
+    }
+
+    // The displacement from the target `t` seconds after release, starting
+    // at -1, and a bound on its size from then on. `None` for springs that
+    // cannot move, which are at their target right away.
+    fn motion(&self, t: f32) -> Option<(f32, f32)> {
+        if self.stiffness <= 0.0 || self.mass <= 0.0 || self.damping < 0.0 {
+            return None;
+        }
+        let omega = (self.stiffness / self.mass).sqrt();
+        let zeta = self.damping / (2.0 * (self.stiffness * self.mass).sqrt());
+        if (zeta - 1.0).abs() < 1e-4 {
+            // Critically damped.
+            let displacement = -(1.0 + omega * t) * (-omega * t).exp();
+            Some((displacement, displacement.abs()))
+        } else if zeta < 1.0 {
+            let frequency = omega * (1.0 - zeta * zeta).sqrt();
+            let decay = (-zeta * omega * t).exp();
+            let ratio = zeta * omega / frequency;
+            let displacement = -decay * ((frequency * t).cos() + ratio * (frequency * t).sin());
+            Some((displacement, decay * (1.0 + ratio * ratio).sqrt()))
+        } else {
+            let root = (zeta * zeta - 1.0).sqrt();
+            let slow = -omega * (zeta - root);
+            let fast = -omega * (zeta + root);
+            // Starts at -1, at rest.
+            let a = fast / (slow - fast);
+            let b = -1.0 - a;
+            let displacement = a * (slow * t).exp() + b * (fast * t).exp();
+            Some((displacement, a.abs() * (slow * t).exp() + b.abs() * (fast * t).exp()))
+        }
+    }
+}
+
+/// A value an animation passes through at a given time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    /// From the start of the animation.
+    pub at: Duration,
+    pub value: T,
+    /// How progress is spread between the previous keyframe and this one.
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(at: Duration, value: T) -> Keyframe<T> {
+        Keyframe { at, value, easing: Easing::Linear }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// How many times an animation plays.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Repeat {
+    #[default]
+    Once,
+    /// At least once.
+    Times(u32),
+    /// Until cancelled, never completing.
+    Forever,
+}
+
+enum Curve<T> {
+    Tween { to: T, duration: Duration, easing: Easing },
+    // The settle time is worked out once, up front.
+    Spring { to: T, spring: Spring, duration: Duration },
+    Keyframes(Vec<Keyframe<T>>),
+}
+
+impl<T: Animatable> Curve<T> {
+    fn duration(&self) -> Duration {
+        match self {
+            Curve::Tween { duration, .. } | Curve::Spring { duration, .. } => *duration,
+            Curve::Keyframes(keyframes) => keyframes.last().map_or(Duration::ZERO, |keyframe| keyframe.at),
+        }
+    }
+
+    fn value(&self, from: T, time: Duration) -> T {
+        match self {
+            Curve::Tween { to, duration, easing } => {
+                let t = if duration.is_zero() { 1.0 } else { time.as_secs_f32() / duration.as_secs_f32() };
+                from.lerp(to, easing.apply(t))
+            }
+            Curve::Spring { to, duration, .. } if time >= *duration => *to,
+            Curve::Spring { to, spring, .. } => from.lerp(to, spring.progress(time)),
+            Curve::Keyframes(keyframes) => {
+                let (mut start, mut value) = (Duration::ZERO, from);
+                for keyframe in keyframes {
+                    if time <= keyframe.at {
+                        let span = keyframe.at - start;
+                        let t = if span.is_zero() { 1.0 } else { (time - start).as_secs_f32() / span.as_secs_f32() };
+                        return value.lerp(&keyframe.value, keyframe.easing.apply(t));
+                    }
+                    (start, value) = (keyframe.at, keyframe.value);
+                }
+                value
+            }
+        }
+    }
+}
+
+type Callback = Box<dyn FnOnce(&mut Animator, &mut Scene)>;
+
+/// A change of one property of an element over time, started with
+/// [`Animator::start`].
+pub struct Animation<T> {
+    property: Property<T>,
+    from: Option<T>,
+    curve: Curve<T>,
+    delay: Duration,
+    repeat: Repeat,
+    alternate: bool,
+    on_complete: Option<Callback>,
+    // Since the animation was started, including the delay.
+    elapsed: Duration,
+}
+
+impl<T: Animatable> Animation<T> {
+    fn new(property: Property<T>, curve: Curve<T>) -> Animation<T> {
+        Animation {
+            property,
+            from: None,
+            curve,
+            delay: Duration::ZERO,
+            repeat: Repeat::Once,
+            alternate: false,
+            on_complete: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Moves `property` to `to` over `duration`, evenly unless eased with
+    /// [`Animation::with_easing`].
+    pub fn tween(property: Property<T>, to: T, duration: Duration) -> Animation<T> {
+        Animation::new(property, Curve::Tween { to, duration, easing: Easing::Linear })
+    }
+
+    /// Moves `property` to `to` like `spring` would.
+    pub fn spring(property: Property<T>, to: T, spring: Spring) -> Animation<T> {
+        Animation::new(property, Curve::Spring { to, spring, duration: spring.settle_time() })
+    }
+
+    /// Moves `property` through each of `keyframes` in turn, ending at the
+    /// last. Without a keyframe at zero, the animation starts from the
+    /// value the property has.
+    pub fn keyframes(property: Property<T>, keyframes: impl IntoIterator<Item = Keyframe<T>>) -> Animation<T> {
+        let mut keyframes: Vec<Keyframe<T>> = keyframes.into_iter().collect();
+        keyframes.sort_by_key(|keyframe| keyframe.at);
+        Animation::new(property, Curve::Keyframes(keyframes))
+    }
+
+    /// Starts from `from` instead of the value the property has once the
+    /// animation starts.
+    pub fn starting_from(mut self, from: T) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Eases a tween. Keyframes are eased one by one, and springs not at
+    /// all.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        if let Curve::Tween { easing: current, .. } = &mut self.curve {
+            *current = easing;
+        }
+        self
+    }
+
+    /// Waits for `delay` before starting, once, not before every repeat.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Plays every other repeat backwards, so the property goes back and
+    /// forth.
+    pub fn with_alternate(mut self, alternate: bool) -> Self {
+        self.alternate = alternate;
+        self
+    }
+
+    /// Calls `callback` once the animation has played to its end, after
+    /// every animation has been advanced for the frame. It is not called for
+    /// animations that are cancelled or replaced, or whose element is
+    /// removed.
+    pub fn on_complete(mut self, callback: impl FnOnce(&mut Animator, &mut Scene) + 'static) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    /// How long playing once takes, without the delay.
+    pub fn duration(&self) -> Duration {
+        self.curve.duration()
+    }
+}
+
+enum Status {
+    Running,
+    Finished,
+    // The element is no longer in the scene.
+    Gone,
+}
+
+// Animations of any property, for keeping them in one list.
+trait Advance {
+    fn property(&self) -> &'static str;
+    fn advance(&mut self, scene: &mut Scene, id: ElementId, delta: Duration) -> Status;
+    fn take_on_complete(&mut self) -> Option<Callback>;
+}
+
+impl<T: Animatable> Advance for Animation<T> {
+    fn property(&self) -> &'static str {
+        self.property.name
+    }
+
+    fn advance(&mut self, scene: &mut Scene, id: ElementId, delta: Duration) -> Status {
+        let Some(element) = scene.get(id) else {
+            return Status::Gone;
+        };
+        self.elapsed += delta;
+        let Some(time) = self.elapsed.checked_sub(self.delay) else {
+            return Status::Running;
+        };
+        let from = *self.from.get_or_insert_with(|| (self.property.get)(element));
+
+        let duration = self.curve.duration();
+        let plays = match self.repeat {
+            Repeat::Once => 1,
+            Repeat::Times(times) => times.max(1) as u128,
+            Repeat::Forever => u128::MAX,
+        };
+        let (play, time, finished) = match time.as_nanos().checked_div(duration.as_nanos()) {
+            Some(play) if play < plays => {
+                let time = Duration::from_nanos((time.as_nanos() % duration.as_nanos()) as u64);
+                (play, time, false)
+            }
+            // Played to the end, or takes no time at all.
+            _ => (plays - 1, duration, true),
+        };
+        let time = if self.alternate && play % 2 == 1 { duration - time } else { time };
+        (self.property.set)(scene, id, self.curve.value(from, time));
+        if finished { Status::Finished } else { Status::Running }
+    }
+
+    fn take_on_complete(&mut self) -> Option<Callback> {
+        self.on_complete.take()
+    }
+}
+
+/// Identifies an animation started with [`Animator::start`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationId(u64);
+
+struct Running {
+    id: AnimationId,
+    element: ElementId,
+    animation: Box<dyn Advance>,
+}
+
+/// The animations running in a scene.
+#[derive(Default)]
+pub struct Animator {
+    next_id: u64,
+    running: Vec<Running>,
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator::default()
+    }
+
+    /// Starts animating a property of `element` on the next update,
+    /// replacing any animation of the same property already running on it.
+    pub fn start<T: Animatable + 'static>(&mut self, element: ElementId, animation: Animation<T>) -> AnimationId {
+        let id = AnimationId(self.next_id);
+        self.next_id += 1;
+        self.running
+            .retain(|running| running.element != element || running.animation.property() != animation.property.name);
+        self.running.push(Running { id, element, animation: Box::new(animation) });
+        id
+    }
+
+    /// Stops an animation where it is. Returns whether it was running.
+    pub fn cancel(&mut self, id: AnimationId) -> bool {
+        let count = self.running.len();
+        self.running.retain(|running| running.id != id);
+        self.running.len() != count
+    }
+
+    /// Stops every animation of `element`.
+    pub fn cancel_all(&mut self, element: ElementId) {
+        self.running.retain(|running| running.element != element);
+    }
+
+    pub fn is_running(&self, id: AnimationId) -> bool {
+        self.running.iter().any(|running| running.id == id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Advances every animation by `delta` and writes their values into the
+    /// elements of `scene`, then calls the callbacks of those that completed.
+    /// Animations of elements that are no longer in the scene are dropped.
+    pub fn update(&mut self, scene: &mut Scene, delta: Duration) {
+        let mut completed = Vec::new();
+        self.running.retain_mut(|running| match running.animation.advance(scene, running.element, delta) {
+            Status::Running => true,
+            Status::Finished => {
+                completed.extend(running.animation.take_on_complete());
+                false
+            }
+            Status::Gone => false,
+        });
+        for callback in completed {
+            callback(self, scene);
+        }
+    }
+}
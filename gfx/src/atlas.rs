@@ -0,0 +1,223 @@
+// Glyph atlas: rasterized glyph coverage packed into one texture.
+//
+// Glyphs are packed into shelves on the CPU copy, which is uploaded whenever
+// new glyphs were added. When the atlas runs out of rows it doubles in height;
+// vertices address it in texels, so existing glyphs keep their coordinates.
+
+use std::collections::HashMap;
+
+use fontdue::layout::GlyphRasterConfig;
+
+const INITIAL_SIZE: u32 = 512;
+const MAX_HEIGHT: u32 = 8192;
+// Empty texels around each glyph, so linear filtering never bleeds into a
+// neighbour.
+const PADDING: u32 = 1;
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct AtlasGlyph {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub(crate) struct GlyphAtlas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    shelf_x: u32,
+    shelf_y: u32,
+    shelf_height: u32,
+    // `None` for glyphs without any coverage, like spaces.
+    glyphs: HashMap<GlyphRasterConfig, Option<AtlasGlyph>>,
+    dirty: bool,
+}
+
+impl GlyphAtlas {
+    pub(crate) fn new() -> GlyphAtlas {
+        GlyphAtlas {
+            width: INITIAL_SIZE,
+            height: INITIAL_SIZE,
+            pixels: vec![0; (INITIAL_SIZE * INITIAL_SIZE) as usize],
+            shelf_x: PADDING,
+            shelf_y: PADDING,
+            shelf_height: 0,
+            glyphs: HashMap::new(),
+            dirty: true,
+        }
+    }
+
+    /// Where the glyph lives in the atlas, rasterizing it on first use.
+    pub(crate) fn glyph(&mut self, font: &fontdue::Font, key: GlyphRasterConfig) -> Option<AtlasGlyph> {
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return *glyph;
+        }
+
+        let (metrics, coverage) = font.rasterize_config(key);
+        let glyph = if metrics.width == 0 || metrics.height == 0 {
+            None
+        } else {
+            self.insert(metrics.width as u32, metrics.height as u32, &coverage)
+        };
+        self.glyphs.insert(key, glyph);
+        glyph
+    }
+
+    fn insert(&mut self, width: u32, height: u32, coverage: &[u8]) -> Option<AtlasGlyph> {
+        if width + 2 * PADDING > self.width {
+            log::warn!("glyph of {width}x{height} does not fit in the atlas");
+            return None;
+        }
+
+        if self.shelf_x + width + PADDING > self.width {
+            self.shelf_y += self.shelf_height + PADDING;
+            self.shelf_x = PADDING;
+            self.shelf_height = 0;
+        }
+        while self.shelf_y + height + PADDING > self.height {
+            if self.height * 2 > MAX_HEIGHT {
+                log::warn!("glyph atlas is full");
+                return None;
+            }
+            self.height *= 2;
+            self.pixels.resize((self.width * self.height) as usize, 0);
+        }
+
+        let (x, y) = (self.shelf_x, self.shelf_y);
+        for row in 0..height {
+            let src = (row * width) as usize;
+            let dst = ((y + row) * self.width + x) as usize;
+            self.pixels[dst..dst + width as usize].copy_from_slice(&coverage[src..src + width as usize]);
+        }
+        self.shelf_x += width + PADDING;
+        self.shelf_height = self.shelf_height.max(height);
+        self.dirty = true;
+
+        Some(AtlasGlyph { x, y, width, height })
+    }
+}
+
+/// The GPU copy of a [`GlyphAtlas`].
+pub(crate) struct AtlasTexture {
+    texture: wgpu::Texture,
+    sampler: wgpu::Sampler,
+    bind_group: wgpu::BindGroup,
+}
+
+impl AtlasTexture {
+    pub(crate) fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Glyph Atlas Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    pub(crate) fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, atlas: &GlyphAtlas) -> AtlasTexture {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let (texture, bind_group) = Self::create(device, layout, &sampler, atlas);
+        AtlasTexture { texture, sampler, bind_group }
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        atlas: &GlyphAtlas,
+    ) -> (wgpu::Texture, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: wgpu::Extent3d {
+                width: atlas.width,
+                height: atlas.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Atlas Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        (texture, bind_group)
+    }
+
+    /// Brings the texture up to date with glyphs added since the last upload.
+    pub(crate) fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        atlas: &mut GlyphAtlas,
+    ) {
+        if !atlas.dirty {
+            return;
+        }
+        atlas.dirty = false;
+
+        if self.texture.height() != atlas.height {
+            (self.texture, self.bind_group) = Self::create(device, layout, &self.sampler, atlas);
+        }
+
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &atlas.pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(atlas.width),
+                rows_per_image: Some(atlas.height),
+            },
+            wgpu::Extent3d {
+                width: atlas.width,
+                height: atlas.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
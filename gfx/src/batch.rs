@@ -0,0 +1,208 @@
+// Batching of element draws into as few draw calls as possible.
+//
+// Every element asks for one draw per material and element of its tree. Draws
+// that share a pipeline, bind groups and instances are merged into a batch,
+// whose indices are copied next to each other into one index range. A draw
+// may only join a batch drawn earlier when it overlaps nothing drawn in
+// between, so what ends up on top stays the same as in scene order. Draws
+// clipped by an ancestor carry their scissor rectangle, and only join batches
+// clipped to the same one. Nothing moves across the mask draws that change
+// the stencil buffer, since what is drawn on either side sees different
+// masks.
+
+use std::ops::Range;
+
+use crate::{
+    transform::Transform,
+    ui::{MaskOp, Material},
+};
+
+// How many batches back a draw looks for one to join.
+const MAX_LOOKBACK: usize = 256;
+
+/// What drawing the last frame took.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// Draws the elements asked for, before batching: one per material and
+    /// element of each tree.
+    pub draws: u32,
+    /// Indexed draw calls issued after batching.
+    pub draw_calls: u32,
+    pub pipeline_switches: u32,
+    /// Bind groups set for materials and transforms.
+    pub bind_group_switches: u32,
+}
+
+/// An axis-aligned box around something drawn, in window coordinates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Bounds {
+    min: [f32; 2],
+    max: [f32; 2],
+}
+
+impl Bounds {
+    pub(crate) const EMPTY: Bounds = Bounds {
+        min: [f32::INFINITY; 2],
+        max: [f32::NEG_INFINITY; 2],
+    };
+
+    pub(crate) fn new(min: [f32; 2], max: [f32; 2]) -> Bounds {
+        Bounds { min, max }
+    }
+
+    pub(crate) fn add(&mut self, point: [f32; 2]) {
+        for axis in 0..2 {
+            self.min[axis] = self.min[axis].min(point[axis]);
+            self.max[axis] = self.max[axis].max(point[axis]);
+        }
+    }
+
+    pub(crate) fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            min: [0, 1].map(|axis| self.min[axis].min(other.min[axis])),
+            max: [0, 1].map(|axis| self.max[axis].max(other.max[axis])),
+        }
+    }
+
+    pub(crate) fn intersection(self, other: Bounds) -> Bounds {
+        Bounds {
+            min: [0, 1].map(|axis| self.min[axis].max(other.min[axis])),
+            max: [0, 1].map(|axis| self.max[axis].min(other.max[axis])),
+        }
+    }
+
+    /// Whether the box covers no area.
+    pub(crate) fn is_empty(&self) -> bool {
+        (0..2).any(|axis| self.min[axis] >= self.max[axis])
+    }
+
+    /// The bounds of this box after `transform`.
+    pub(crate) fn transformed(&self, transform: &Transform) -> Bounds {
+        let mut bounds = Bounds::EMPTY;
+        if self.min[0] > self.max[0] {
+            return bounds;
+        }
+        for corner in [self.min, [self.max[0], self.min[1]], self.max, [self.min[0], self.max[1]]] {
+            bounds.add(transform.apply(corner));
+        }
+        bounds
+    }
+
+    // Touching counts, since both may cover the pixels on the edge.
+    fn overlaps(&self, other: &Bounds) -> bool {
+        (0..2).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+}
+
+/// A rectangle of the target that draws are clipped to, in physical pixels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Scissor {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Scissor {
+    /// The pixels whose centers are inside `bounds`, which are in logical
+    /// pixels.
+    pub(crate) fn new(bounds: &Bounds, scale_factor: f32) -> Scissor {
+        let [x, y] = bounds.min.map(|v| (v * scale_factor).round().max(0.0) as u32);
+        let [max_x, max_y] = bounds.max.map(|v| (v * scale_factor).round().max(0.0) as u32);
+        Scissor { x, y, width: max_x.saturating_sub(x), height: max_y.saturating_sub(y) }
+    }
+
+    /// The part of the rectangle inside a target of `width` by `height`
+    /// pixels.
+    pub(crate) fn within(&self, width: u32, height: u32) -> Scissor {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Scissor {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// Everything a draw call binds besides the shared buffers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DrawState {
+    pub material: Material,
+    /// Dynamic offset of the transform.
+    pub transform_offset: u32,
+    pub instances: Range<u32>,
+    /// `None` when no ancestor clips the draw.
+    pub clip: Option<Scissor>,
+    /// Stencil reference: how many masks the draw is inside.
+    pub stencil: u32,
+    pub mask: Option<MaskOp>,
+}
+
+/// One draw of an element, in scene order.
+pub(crate) struct ElementDraw<'a> {
+    pub state: DrawState,
+    pub bounds: Bounds,
+    /// Indices into the shared vertex buffer.
+    pub indices: &'a [u32],
+}
+
+/// One draw call.
+pub(crate) struct Batch {
+    pub state: DrawState,
+    /// Range of the batched index buffer.
+    pub indices: Range<u32>,
+}
+
+struct Group<'a> {
+    state: DrawState,
+    bounds: Bounds,
+    parts: Vec<&'a [u32]>,
+}
+
+/// Merges `draws` into batches, returning them in draw order together with
+/// the indices they draw.
+pub(crate) fn batch<'a>(draws: impl IntoIterator<Item = ElementDraw<'a>>) -> (Vec<Batch>, Vec<u32>) {
+    let mut groups: Vec<Group> = Vec::new();
+    for draw in draws {
+        if draw.indices.is_empty() {
+            continue;
+        }
+        let mut joined = None;
+        for (i, group) in groups.iter().enumerate().rev().take(MAX_LOOKBACK) {
+            if group.state == draw.state {
+                joined = Some(i);
+                break;
+            }
+            if group.state.mask.is_some() || group.bounds.overlaps(&draw.bounds) {
+                break;
+            }
+        }
+        match joined {
+            Some(i) => {
+                let group = &mut groups[i];
+                group.bounds = group.bounds.union(draw.bounds);
+                group.parts.push(draw.indices);
+            }
+            None => groups.push(Group { state: draw.state, bounds: draw.bounds, parts: vec![draw.indices] }),
+        }
+    }
+
+    let mut indices = Vec::new();
+    let batches = groups
+        .into_iter()
+        .map(|group| {
+            let start = indices.len() as u32;
+            for part in group.parts {
+                indices.extend_from_slice(part);
+            }
+            Batch { state: group.state, indices: start..indices.len() as u32 }
+        })
+        .collect();
+    (batches, indices)
+}
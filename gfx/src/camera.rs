@@ -0,0 +1,101 @@
+// Maps element coordinates, given in logical pixels with the origin in the
+// top-left corner and y pointing down, to clip space.
+
+use wgpu::util::DeviceExt;
+
+// Depth range of the projection, in the units of `Vertex::position[2]`. The
+// back maps to depth 0 and the front to depth 1.
+pub(crate) const Z_BACK: f32 = -1.0;
+pub(crate) const Z_FRONT: f32 = 1.0;
+// Depth between consecutive z-indices. Larger z is in front.
+pub(crate) const Z_INDEX_STEP: f32 = 1.0 / 4096.0;
+
+// The depth of a point at `z` on an element with the given effective
+// z-index, kept inside the projection so nothing is clipped away.
+pub(crate) fn layer_depth(z: f32, z_index: i32) -> f32 {
+    (z + z_index as f32 * Z_INDEX_STEP).clamp(Z_BACK, Z_FRONT)
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+}
+
+pub(crate) struct Camera {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Camera {
+    pub(crate) fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        size: winit::dpi::PhysicalSize<u32>,
+        scale_factor: f64,
+    ) -> Camera {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::bytes_of(&CameraUniform::new(size, scale_factor)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Camera { buffer, bind_group }
+    }
+
+    pub(crate) fn update(&self, queue: &wgpu::Queue, size: winit::dpi::PhysicalSize<u32>, scale_factor: f64) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&CameraUniform::new(size, scale_factor)));
+    }
+
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+impl CameraUniform {
+    fn new(size: winit::dpi::PhysicalSize<u32>, scale_factor: f64) -> CameraUniform {
+        let logical = size.to_logical::<f32>(scale_factor);
+        CameraUniform {
+            view_proj: orthographic(0.0, logical.width.max(1.0), logical.height.max(1.0), 0.0, Z_BACK, Z_FRONT),
+        }
+    }
+}
+
+// Column-major orthographic projection onto wgpu's clip space, where depth
+// goes from 0 at `back` to 1 at `front`.
+fn orthographic(left: f32, right: f32, bottom: f32, top: f32, back: f32, front: f32) -> [[f32; 4]; 4] {
+    let width = right - left;
+    let height = top - bottom;
+    let depth = front - back;
+    [
+        [2.0 / width, 0.0, 0.0, 0.0],
+        [0.0, 2.0 / height, 0.0, 0.0],
+        [0.0, 0.0, 1.0 / depth, 0.0],
+        [-(right + left) / width, -(top + bottom) / height, -back / depth, 1.0],
+    ]
+}
@@ -0,0 +1,58 @@
+// Frame timing for animations.
+//
+// The app ticks the clock once per frame, before the scene is rendered, and
+// advances every animation by the time since the previous tick.
+
+use std::time::{Duration, Instant};
+
+/// The longest a single tick advances by, so animations do not jump ahead
+/// after the app stalls, for example while its window was hidden.
+pub const MAX_FRAME_DELTA: Duration = Duration::from_millis(100);
+
+/// Measures the time between frames.
+#[derive(Clone, Debug, Default)]
+pub struct FrameClock {
+    last: Option<Instant>,
+    elapsed: Duration,
+    delta: Duration,
+    frame: u64,
+}
+
+impl FrameClock {
+    pub fn new() -> FrameClock {
+        FrameClock::default()
+    }
+
+    /// Starts a frame now and returns how long the previous one took, at
+    /// most [`MAX_FRAME_DELTA`]. The first frame takes no time.
+    pub fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        let delta = self.last.map_or(Duration::ZERO, |last| now - last);
+        self.last = Some(now);
+        self.advance(delta.min(MAX_FRAME_DELTA))
+    }
+
+    /// Starts a frame `delta` after the previous one, for driving the clock
+    /// by hand. Returns `delta`.
+    pub fn advance(&mut self, delta: Duration) -> Duration {
+        self.elapsed += delta;
+        self.delta = delta;
+        self.frame += 1;
+        delta
+    }
+
+    /// The time since the previous frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// The time the clock has advanced by in total.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Frames started so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
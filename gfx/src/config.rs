@@ -0,0 +1,221 @@
+// Settings chosen by the application when the renderer is created.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+pub use wgpu::{Backends, CompositeAlphaMode, PowerPreference, PresentMode};
+
+/// How colors are encoded in the frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Element colors are linear and encoded to sRGB on write, so blending
+    /// and gradients happen in linear light.
+    #[default]
+    Srgb,
+    /// Element colors are written to the frame as they are.
+    Linear,
+}
+
+/// How the renderer is set up. Choices the adapter or surface cannot honour
+/// make creating the renderer fail, except for the composite alpha mode and
+/// the sample count, which fall back to the closest supported option.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RendererConfig {
+    /// Graphics APIs to look for an adapter on. `None` looks on the primary
+    /// ones for a window, and on all of them offscreen, where GL is often
+    /// the only one available.
+    pub backends: Option<Backends>,
+    pub power_preference: PowerPreference,
+    /// Only considers software adapters.
+    pub force_fallback_adapter: bool,
+    pub color_space: ColorSpace,
+    pub present_mode: PresentMode,
+    /// Frames the CPU may queue up ahead of the display.
+    pub max_frame_latency: u32,
+    /// How the window is blended with whatever is behind it.
+    /// `CompositeAlphaMode::Auto` picks the first supported of pre-multiplied,
+    /// inherited and opaque, so windows created transparent show the desktop
+    /// through wherever nothing is drawn.
+    pub composite_alpha: CompositeAlphaMode,
+    /// Samples per pixel for anti-aliasing: 1 (off), 2, 4 or 8. Counts the
+    /// adapter cannot render fall back to the next lower supported one.
+    pub sample_count: u32,
+    /// Adds a depth buffer, so overlapping geometry is sorted by the z of its
+    /// vertices (larger in front) rather than by draw order.
+    pub depth_buffer: bool,
+    /// Adds a stencil buffer for elements that mask their descendants, see
+    /// `Element::with_mask`. Without it those are drawn unmasked.
+    pub masks: bool,
+    /// A directory to load the shaders from and watch while running, for
+    /// working on them without rebuilding. Files are named like the shaders
+    /// in `gfx/src`, which can be used directly; missing ones stay built in.
+    pub shader_dir: Option<PathBuf>,
+    /// Draws a graph of recent frame times over the top-left corner.
+    pub profiler_overlay: bool,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        RendererConfig {
+            backends: None,
+            power_preference: PowerPreference::default(),
+            force_fallback_adapter: false,
+            color_space: ColorSpace::default(),
+            present_mode: PresentMode::AutoVsync,
+            max_frame_latency: 2,
+            composite_alpha: CompositeAlphaMode::Auto,
+            sample_count: 1,
+            depth_buffer: false,
+            masks: false,
+            shader_dir: None,
+            profiler_overlay: false,
+        }
+    }
+}
+
+impl RendererConfig {
+    pub fn with_backends(mut self, backends: Backends) -> Self {
+        self.backends = Some(backends);
+        self
+    }
+
+    pub fn with_power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn with_fallback_adapter(mut self, force_fallback_adapter: bool) -> Self {
+        self.force_fallback_adapter = force_fallback_adapter;
+        self
+    }
+
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    pub fn with_max_frame_latency(mut self, max_frame_latency: u32) -> Self {
+        self.max_frame_latency = max_frame_latency;
+        self
+    }
+
+    pub fn with_composite_alpha(mut self, composite_alpha: CompositeAlphaMode) -> Self {
+        self.composite_alpha = composite_alpha;
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn with_depth_buffer(mut self, depth_buffer: bool) -> Self {
+        self.depth_buffer = depth_buffer;
+        self
+    }
+
+    pub fn with_masks(mut self, masks: bool) -> Self {
+        self.masks = masks;
+        self
+    }
+
+    pub fn with_shader_dir(mut self, shader_dir: impl Into<PathBuf>) -> Self {
+        self.shader_dir = Some(shader_dir.into());
+        self
+    }
+
+    pub fn with_profiler_overlay(mut self, profiler_overlay: bool) -> Self {
+        self.profiler_overlay = profiler_overlay;
+        self
+    }
+
+    /// Whether the window should be created transparent.
+    pub fn is_transparent(&self) -> bool {
+        self.composite_alpha != CompositeAlphaMode::Opaque
+    }
+}
+
+// Frames are rendered with pre-multiplied alpha, so that is what the
+// compositor should expect. Post-multiplied compositing would darken every
+// translucent edge and is only used when asked for explicitly.
+const ALPHA_MODE_ORDER: [CompositeAlphaMode; 3] = [
+    CompositeAlphaMode::PreMultiplied,
+    CompositeAlphaMode::Inherit,
+    CompositeAlphaMode::Opaque,
+];
+
+/// The composite alpha mode to configure a surface with, given the preferred
+/// mode and the modes in the surface capabilities.
+pub fn pick_alpha_mode(
+    preferred: CompositeAlphaMode,
+    supported: &[CompositeAlphaMode],
+) -> CompositeAlphaMode {
+    if preferred != CompositeAlphaMode::Auto && supported.contains(&preferred) {
+        return preferred;
+    }
+    if preferred != CompositeAlphaMode::Auto {
+        log::warn!("composite alpha mode {preferred:?} is not supported, falling back");
+    }
+    ALPHA_MODE_ORDER
+        .into_iter()
+        .find(|mode| supported.contains(mode))
+        .or_else(|| supported.first().copied())
+        .unwrap_or(CompositeAlphaMode::Auto)
+}
+
+/// The sample count to render with, given the requested one and the features
+/// of the target format on the adapter.
+pub fn pick_sample_count(requested: u32, features: wgpu::TextureFormatFeatureFlags) -> u32 {
+    let requested = match requested {
+        0 | 1 => return 1,
+        2 | 4 | 8 => requested,
+        _ => {
+            log::warn!("{requested}x MSAA is not one of 1, 2, 4 or 8");
+            requested.min(8)
+        }
+    };
+    let count = [8, 4, 2]
+        .into_iter()
+        .filter(|count| *count <= requested)
+        .find(|count| features.sample_count_supported(*count))
+        .unwrap_or(1);
+    if count != requested {
+        log::warn!("{requested}x MSAA is not supported, using {count}x");
+    }
+    count
+}
+
+/// The surface format to configure and the format of the views rendered into
+/// it, given the formats in the surface capabilities.
+pub fn pick_format(
+    color_space: ColorSpace,
+    supported: &[wgpu::TextureFormat],
+) -> anyhow::Result<(wgpu::TextureFormat, wgpu::TextureFormat)> {
+    anyhow::ensure!(!supported.is_empty(), "the surface is not compatible with the adapter");
+    match color_space {
+        ColorSpace::Srgb => supported
+            .iter()
+            .find(|format| format.add_srgb_suffix().is_srgb())
+            .map(|format| (*format, format.add_srgb_suffix()))
+            .with_context(|| format!("none of the surface formats {supported:?} has an sRGB view")),
+        ColorSpace::Linear => {
+            let format = supported.iter().find(|format| !format.is_srgb()).unwrap_or(&supported[0]);
+            Ok((*format, format.remove_srgb_suffix()))
+        }
+    }
+}
+
+/// Checks the present mode against the modes in the surface capabilities.
+/// The automatic modes are always available.
+pub fn check_present_mode(mode: PresentMode, supported: &[PresentMode]) -> anyhow::Result<PresentMode> {
+    match mode {
+        PresentMode::AutoVsync | PresentMode::AutoNoVsync => Ok(mode),
+        _ if supported.contains(&mode) => Ok(mode),
+        _ => anyhow::bail!("present mode {mode:?} is not supported, the surface offers {supported:?}"),
+    }
+}
@@ -0,0 +1,29 @@
+// Errors the renderer cannot recover from on its own.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The GPU ran out of memory for the next frame.
+    OutOfMemory,
+    /// Acquiring a frame failed, and reconfiguring the surface does not help.
+    Surface(wgpu::SurfaceError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::OutOfMemory => write!(f, "out of GPU memory"),
+            RenderError::Surface(err) => write!(f, "failed to acquire a frame: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::OutOfMemory => None,
+            RenderError::Surface(err) => Some(err),
+        }
+    }
+}
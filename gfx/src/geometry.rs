@@ -0,0 +1,452 @@
+// GPU storage for the tessellated scene.
+//
+// Every element owns a slot in one shared vertex buffer. Changed elements are
+// rewritten in place when their new mesh fits in the old slot, and get a fresh
+// slot at the end otherwise. Buffers grow by doubling (copying the old
+// contents on the GPU), and the vertex buffer is only compacted once more
+// than half of it is unused.
+//
+// Indices, transforms and instances are rebuilt on the CPU whenever an
+// element changes or moves: indices are regrouped into batches (see `batch`)
+// every time, so they have no slots of their own. Rebuilding is cheap next to
+// tessellation; uploading is not, so each of them keeps a copy of what its
+// buffer holds and only the range between the first and last item that
+// differ is written. Moving one element then rewrites one transform.
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    batch::{self, Batch, Bounds, DrawState, ElementDraw, Scissor},
+    instance::InstanceRaw,
+    scene::{ElementId, Scene},
+    state::Vertex,
+    transform::{Transform, TransformBuffer},
+    ui::{BuildContext, Element, MeshDraw},
+};
+
+const VERTEX_SIZE: u64 = std::mem::size_of::<Vertex>() as u64;
+const INDEX_SIZE: u64 = std::mem::size_of::<u32>() as u64;
+const INSTANCE_SIZE: u64 = std::mem::size_of::<InstanceRaw>() as u64;
+
+const INITIAL_VERTICES: u64 = 1024;
+const INITIAL_INDICES: u64 = 4096;
+const INITIAL_INSTANCES: u64 = 256;
+
+// Wasted space below this (in vertices) is never worth a compaction.
+const MIN_COMPACT_WASTE: u32 = 4096;
+
+struct GrowableBuffer {
+    buffer: wgpu::Buffer,
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+}
+
+impl GrowableBuffer {
+    fn new(device: &wgpu::Device, label: &'static str, usage: wgpu::BufferUsages, size: u64) -> Self {
+        let usage = usage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage,
+            mapped_at_creation: false,
+        });
+        GrowableBuffer { buffer, label, usage }
+    }
+
+    // Makes room for at least `size` bytes, keeping the current contents.
+    // Returns whether a copy was recorded into `encoder`.
+    fn reserve(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, size: u64) -> bool {
+        let old_size = self.buffer.size();
+        if size <= old_size {
+            return false;
+        }
+
+        let new_size = size.next_power_of_two().max(old_size * 2);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(self.label),
+            size: new_size,
+            usage: self.usage,
+            mapped_at_creation: false,
+        });
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, old_size);
+        self.buffer = buffer;
+        true
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Slot {
+    vertex_start: u32,
+    vertex_capacity: u32,
+}
+
+struct Allocation {
+    slot: Slot,
+    draws: Vec<MeshDraw>,
+    // The indices of the mesh, pointing into the shared vertex buffer.
+    indices: Vec<u32>,
+    // Untransformed bounds of each draw.
+    bounds: Vec<Bounds>,
+    // World transforms of the element's tree, indexed by `MeshDraw::node`.
+    transforms: Vec<Transform>,
+    // What ancestors clip each element of the tree to, in window coordinates.
+    clips: Vec<Option<Bounds>>,
+    // `None` for elements drawn once, with the identity instance.
+    instances: Option<Vec<InstanceRaw>>,
+}
+
+pub(crate) struct SceneBuffers {
+    vertices: GrowableBuffer,
+    // Batched indices, in draw order.
+    indices: GrowableBuffer,
+    transforms: TransformBuffer,
+    instances: GrowableBuffer,
+    // What the index and instance buffers hold.
+    written_indices: Vec<u32>,
+    written_instances: Vec<InstanceRaw>,
+    slots: HashMap<ElementId, Allocation>,
+    batches: Vec<Batch>,
+    // Element draws that went into the batches.
+    draw_count: u32,
+    // Bump allocator, in vertices.
+    vertex_end: u32,
+    wasted_vertices: u32,
+    // Logical size and scale factor the current slots were built for.
+    viewport: [f32; 2],
+    scale_factor: f32,
+}
+
+impl SceneBuffers {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        Self::with_transforms(device, TransformBuffer::new(device))
+    }
+
+    /// Buffers for another scene, drawn with the same pipelines as `other`.
+    pub(crate) fn sharing_layout(device: &wgpu::Device, other: &SceneBuffers) -> Self {
+        Self::with_transforms(device, TransformBuffer::with_layout(device, other.transform_layout().clone()))
+    }
+
+    fn with_transforms(device: &wgpu::Device, transforms: TransformBuffer) -> Self {
+        SceneBuffers {
+            vertices: GrowableBuffer::new(
+                device,
+                "Vertex Buffer",
+                wgpu::BufferUsages::VERTEX,
+                INITIAL_VERTICES * VERTEX_SIZE,
+            ),
+            indices: GrowableBuffer::new(
+                device,
+                "Index Buffer",
+                wgpu::BufferUsages::INDEX,
+                INITIAL_INDICES * INDEX_SIZE,
+            ),
+            transforms,
+            instances: GrowableBuffer::new(
+                device,
+                "Instance Buffer",
+                wgpu::BufferUsages::VERTEX,
+                INITIAL_INSTANCES * INSTANCE_SIZE,
+            ),
+            written_indices: Vec::new(),
+            written_instances: Vec::new(),
+            slots: HashMap::new(),
+            batches: Vec::new(),
+            draw_count: 0,
+            vertex_end: 0,
+            wasted_vertices: 0,
+            viewport: [0.0, 0.0],
+            scale_factor: 0.0,
+        }
+    }
+
+    /// Uploads whatever changed in the scene since the last call, laying
+    /// elements out in a viewport of the given logical size.
+    pub(crate) fn sync(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &mut Scene,
+        viewport: [f32; 2],
+        ctx: &mut BuildContext,
+    ) {
+        if viewport != self.viewport || ctx.scale_factor != self.scale_factor {
+            self.viewport = viewport;
+            self.scale_factor = ctx.scale_factor;
+            scene.mark_all_dirty();
+        }
+
+        if self.needs_compaction() {
+            self.slots.clear();
+            self.vertex_end = 0;
+            self.wasted_vertices = 0;
+            scene.mark_all_dirty();
+        }
+
+        let changes = scene.take_changes();
+        let changed = !changes.removed.is_empty() || !changes.dirty.is_empty() || !changes.moved.is_empty();
+        for id in changes.removed {
+            if let Some(allocation) = self.slots.remove(&id) {
+                self.release(allocation.slot);
+            }
+        }
+
+        for id in changes.moved {
+            if let (Some(element), Some(allocation)) = (scene.get(id), self.slots.get_mut(&id)) {
+                allocation.transforms = element.world_transforms();
+                allocation.clips = element.world_clips();
+                allocation.instances = instances(element);
+            }
+        }
+
+        // Allocate every slot first so the buffers only grow once per frame.
+        let mut uploads = Vec::with_capacity(changes.dirty.len());
+        for id in changes.dirty {
+            let Some(element) = scene.layout(id, viewport) else {
+                continue;
+            };
+            let mut mesh = element.build_with(ctx);
+            let vertex_count = mesh.vertices.len() as u32;
+
+            let slot = match self.slots.get(&id).map(|allocation| allocation.slot) {
+                Some(slot) if vertex_count <= slot.vertex_capacity => slot,
+                old => {
+                    if let Some(old) = old {
+                        self.release(old);
+                    }
+                    self.allocate(vertex_count)
+                }
+            };
+            let draws = std::mem::take(&mut mesh.draws);
+            let bounds = draws
+                .iter()
+                .map(|draw| {
+                    let mut bounds = Bounds::EMPTY;
+                    for &index in &mesh.indices[draw.indices.start as usize..draw.indices.end as usize] {
+                        let position = mesh.vertices[index as usize].position;
+                        bounds.add([position[0], position[1]]);
+                    }
+                    bounds
+                })
+                .collect();
+            let allocation = Allocation {
+                slot,
+                indices: mesh.indices.iter().map(|index| index + slot.vertex_start).collect(),
+                draws,
+                bounds,
+                transforms: element.world_transforms(),
+                clips: element.world_clips(),
+                instances: instances(element),
+            };
+            self.slots.insert(id, allocation);
+            uploads.push((slot, mesh.vertices));
+        }
+
+        let mut transforms = Vec::new();
+        // Draws of elements without instances use the first one.
+        let mut instances = vec![InstanceRaw::IDENTITY];
+        let mut indices = Vec::new();
+        if changed {
+            indices = self.batch(scene, &mut transforms, &mut instances);
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Scene Buffer Growth"),
+        });
+        let grew = self.vertices.reserve(device, &mut encoder, self.vertex_end as u64 * VERTEX_SIZE)
+            | self.indices.reserve(device, &mut encoder, indices.len() as u64 * INDEX_SIZE)
+            | self.instances.reserve(device, &mut encoder, instances.len() as u64 * INSTANCE_SIZE);
+        if grew {
+            // The copies into the grown buffers must land before the writes below.
+            queue.submit([encoder.finish()]);
+        }
+
+        for (slot, vertices) in uploads {
+            if !vertices.is_empty() {
+                queue.write_buffer(
+                    &self.vertices.buffer,
+                    slot.vertex_start as u64 * VERTEX_SIZE,
+                    bytemuck::cast_slice(&vertices),
+                );
+            }
+        }
+
+        if changed {
+            write_changes(queue, &self.indices.buffer, &mut self.written_indices, indices);
+            self.transforms.write(device, queue, &transforms);
+            write_changes(queue, &self.instances.buffer, &mut self.written_instances, instances);
+        }
+    }
+
+    // Lays out the transforms and instances of every element, and batches
+    // their draws in scene order. Returns the batched indices.
+    fn batch(&mut self, scene: &Scene, transforms: &mut Vec<Transform>, instances: &mut Vec<InstanceRaw>) -> Vec<u32> {
+        // Elements mostly share a few transforms, often only the identity,
+        // and draws can only be batched when they do.
+        let mut slots: HashMap<[u32; 6], u32> = HashMap::new();
+        let mut draws = Vec::new();
+        let mut draw_count = 0;
+        for id in scene.ids() {
+            let Some(allocation) = self.slots.get(&id) else {
+                continue;
+            };
+            let offsets: Vec<u32> = allocation
+                .transforms
+                .iter()
+                .map(|transform| {
+                    let slot = *slots.entry(transform.matrix().map(f32::to_bits)).or_insert_with(|| {
+                        transforms.push(*transform);
+                        transforms.len() as u32 - 1
+                    });
+                    self.transforms.offset(slot)
+                })
+                .collect();
+
+            let instance_range = match &allocation.instances {
+                None => 0..1,
+                Some(placed) => {
+                    let first = instances.len() as u32;
+                    instances.extend_from_slice(placed);
+                    first..instances.len() as u32
+                }
+            };
+
+            for (draw, bounds) in allocation.draws.iter().zip(&allocation.bounds) {
+                draw_count += 1;
+                let world = allocation.transforms[draw.node as usize];
+                let bounds = bounds.transformed(&world);
+                let indices = &allocation.indices[draw.indices.start as usize..draw.indices.end as usize];
+                let mut push = |bounds: Bounds, clip: Option<Bounds>, instances: Range<u32>| {
+                    let bounds = match clip {
+                        Some(clip) => bounds.intersection(clip),
+                        None => bounds,
+                    };
+                    // Clipped away entirely.
+                    if clip.is_some() && bounds.is_empty() {
+                        return;
+                    }
+                    draws.push(ElementDraw {
+                        state: DrawState {
+                            material: draw.material,
+                            transform_offset: offsets[draw.node as usize],
+                            instances,
+                            clip: clip.map(|clip| Scissor::new(&clip, self.scale_factor)),
+                            stencil: draw.masks,
+                            mask: draw.mask,
+                        },
+                        bounds,
+                        indices,
+                    });
+                };
+                match (&allocation.instances, allocation.clips[draw.node as usize]) {
+                    (None, clip) => push(bounds, clip, instance_range.clone()),
+                    (Some(placed), None) => push(
+                        placed
+                            .iter()
+                            .fold(Bounds::EMPTY, |all, instance| all.union(bounds.transformed(&instance.transform()))),
+                        None,
+                        instance_range.clone(),
+                    ),
+                    // Every instance moves the clip along, which one scissor
+                    // rectangle cannot, so they are drawn one at a time.
+                    (Some(placed), Some(clip)) => {
+                        for (i, instance) in placed.iter().enumerate() {
+                            let transform = instance.transform();
+                            let first = instance_range.start + i as u32;
+                            push(bounds.transformed(&transform), Some(clip.transformed(&transform)), first..first + 1);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.draw_count = draw_count;
+        let (batches, indices) = batch::batch(draws);
+        self.batches = batches;
+        indices
+    }
+
+    pub(crate) fn vertex_buffer(&self) -> &wgpu::Buffer {
+        &self.vertices.buffer
+    }
+
+    pub(crate) fn index_buffer(&self) -> &wgpu::Buffer {
+        &self.indices.buffer
+    }
+
+    pub(crate) fn instance_buffer(&self) -> &wgpu::Buffer {
+        &self.instances.buffer
+    }
+
+    pub(crate) fn transform_layout(&self) -> &wgpu::BindGroupLayout {
+        self.transforms.bind_group_layout()
+    }
+
+    pub(crate) fn transform_bind_group(&self) -> &wgpu::BindGroup {
+        self.transforms.bind_group()
+    }
+
+    /// The draw calls for the scene, in order.
+    pub(crate) fn batches(&self) -> &[Batch] {
+        &self.batches
+    }
+
+    /// How many element draws were merged into the batches.
+    pub(crate) fn draw_count(&self) -> u32 {
+        self.draw_count
+    }
+
+    fn allocate(&mut self, vertex_count: u32) -> Slot {
+        let slot = Slot {
+            vertex_start: self.vertex_end,
+            vertex_capacity: vertex_count,
+        };
+        self.vertex_end += vertex_count;
+        slot
+    }
+
+    fn release(&mut self, slot: Slot) {
+        self.wasted_vertices += slot.vertex_capacity;
+    }
+
+    fn needs_compaction(&self) -> bool {
+        let live_vertices = self.vertex_end - self.wasted_vertices;
+        self.wasted_vertices > MIN_COMPACT_WASTE && self.wasted_vertices > live_vertices
+    }
+}
+
+// Writes the items of `items` that differ from `written`, what `buffer`
+// holds, and remembers them as written.
+fn write_changes<T: bytemuck::Pod + PartialEq>(
+    queue: &wgpu::Queue,
+    buffer: &wgpu::Buffer,
+    written: &mut Vec<T>,
+    items: Vec<T>,
+) {
+    if let Some(changed) = changed_range(written, &items) {
+        let offset = (changed.start * std::mem::size_of::<T>()) as u64;
+        queue.write_buffer(buffer, offset, bytemuck::cast_slice(&items[changed]));
+    }
+    *written = items;
+}
+
+/// The range of `items` that has to be written over `written` to make a
+/// buffer hold `items`: from the first to the last item that differs, up to
+/// the end when `items` is longer. Items past the end of a shorter `items`
+/// are left alone, as nothing draws them.
+pub(crate) fn changed_range<T: PartialEq>(written: &[T], items: &[T]) -> Option<Range<usize>> {
+    let common = written.len().min(items.len());
+    let differs = |&i: &usize| written[i] != items[i];
+    let start = (0..common).find(differs).unwrap_or(common);
+    if start == items.len() {
+        return None;
+    }
+    let end = if items.len() > common { items.len() } else { (start..common).rfind(differs).unwrap() + 1 };
+    Some(start..end)
+}
+
+// The instances of a top-level element, placed at its corner.
+fn instances(element: &Element) -> Option<Vec<InstanceRaw>> {
+    let origin = element.rect().min();
+    let instances = element.instances()?;
+    Some(instances.iter().map(|instance| InstanceRaw::new(instance, origin)).collect())
+}
@@ -0,0 +1,203 @@
+// Gradient paints and their GPU uniforms.
+//
+// Gradients are evaluated per fragment from the position inside the element,
+// so they do not depend on how the shape was tessellated. Like images, a
+// gradient is shared by handle, uploaded on first use and freed with its last
+// handle.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use wgpu::util::DeviceExt;
+
+/// Color stops beyond this many are dropped.
+pub const MAX_STOPS: usize = 16;
+
+static NEXT_GRADIENT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GradientId(u64);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorStop {
+    /// Position along the gradient, from 0 to 1.
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+impl ColorStop {
+    pub fn new(offset: f32, color: [f32; 4]) -> ColorStop {
+        ColorStop { offset, color }
+    }
+}
+
+/// Where a gradient runs, in logical pixels relative to the top-left corner of
+/// the element it fills.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GradientKind {
+    /// From the first stop at `start` to the last at `end`, constant across.
+    Linear { start: [f32; 2], end: [f32; 2] },
+    /// From the first stop at `center` to the last on the circle of `radius`.
+    Radial { center: [f32; 2], radius: f32 },
+    /// Around `center`, a full turn starting at `angle` radians from the +x
+    /// axis towards +y.
+    Conic { center: [f32; 2], angle: f32 },
+}
+
+struct GradientData {
+    id: GradientId,
+    kind: GradientKind,
+    stops: Vec<ColorStop>,
+}
+
+/// A multi-stop gradient. Beyond the first and last stop the gradient keeps
+/// their colors. Cheap to clone.
+#[derive(Clone)]
+pub struct Gradient(Arc<GradientData>);
+
+impl Gradient {
+    /// Stops may be given in any order.
+    pub fn new(kind: GradientKind, stops: impl IntoIterator<Item = ColorStop>) -> Gradient {
+        let mut stops: Vec<ColorStop> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        if stops.len() > MAX_STOPS {
+            log::warn!("gradient has {} color stops, only the first {MAX_STOPS} are used", stops.len());
+            stops.truncate(MAX_STOPS);
+        }
+        let id = GradientId(NEXT_GRADIENT_ID.fetch_add(1, Ordering::Relaxed));
+        Gradient(Arc::new(GradientData { id, kind, stops }))
+    }
+
+    pub fn linear(start: [f32; 2], end: [f32; 2], stops: impl IntoIterator<Item = ColorStop>) -> Gradient {
+        Gradient::new(GradientKind::Linear { start, end }, stops)
+    }
+
+    pub fn radial(center: [f32; 2], radius: f32, stops: impl IntoIterator<Item = ColorStop>) -> Gradient {
+        Gradient::new(GradientKind::Radial { center, radius }, stops)
+    }
+
+    pub fn conic(center: [f32; 2], angle: f32, stops: impl IntoIterator<Item = ColorStop>) -> Gradient {
+        Gradient::new(GradientKind::Conic { center, angle }, stops)
+    }
+
+    pub fn id(&self) -> GradientId {
+        self.0.id
+    }
+
+    pub fn kind(&self) -> GradientKind {
+        self.0.kind
+    }
+
+    pub fn stops(&self) -> &[ColorStop] {
+        &self.0.stops
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GradientUniform {
+    // 0 linear, 1 radial, 2 conic.
+    kind: u32,
+    stop_count: u32,
+    _padding: [u32; 2],
+    // Linear: start and end. Radial: center and radius. Conic: center and
+    // angle.
+    params: [f32; 4],
+    // Four offsets per element, to keep the array stride at 16 bytes.
+    offsets: [[f32; 4]; MAX_STOPS / 4],
+    colors: [[f32; 4]; MAX_STOPS],
+}
+
+impl GradientUniform {
+    fn new(gradient: &Gradient) -> GradientUniform {
+        let (kind, params) = match gradient.kind() {
+            GradientKind::Linear { start, end } => (0, [start[0], start[1], end[0], end[1]]),
+            GradientKind::Radial { center, radius } => (1, [center[0], center[1], radius, 0.0]),
+            GradientKind::Conic { center, angle } => (2, [center[0], center[1], angle, 0.0]),
+        };
+        let mut uniform = GradientUniform {
+            kind,
+            stop_count: gradient.stops().len() as u32,
+            _padding: [0; 2],
+            params,
+            offsets: [[0.0; 4]; MAX_STOPS / 4],
+            colors: [[0.0; 4]; MAX_STOPS],
+        };
+        for (i, stop) in gradient.stops().iter().enumerate() {
+            uniform.offsets[i / 4][i % 4] = stop.offset;
+            uniform.colors[i] = stop.color;
+        }
+        uniform
+    }
+}
+
+struct GpuGradient {
+    gradient: Weak<GradientData>,
+    // The buffer lives on through the bind group.
+    bind_group: wgpu::BindGroup,
+}
+
+/// GPU uniforms for the gradients used by the scene.
+pub(crate) struct GradientCache {
+    layout: wgpu::BindGroupLayout,
+    gradients: HashMap<GradientId, GpuGradient>,
+}
+
+impl GradientCache {
+    pub(crate) fn new(device: &wgpu::Device) -> GradientCache {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Gradient Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        GradientCache { layout, gradients: HashMap::new() }
+    }
+
+    pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Uploads the gradients that are not on the GPU yet, and frees the ones
+    /// nothing refers to anymore.
+    pub(crate) fn upload(&mut self, device: &wgpu::Device, gradients: &[Gradient]) {
+        self.gradients.retain(|_, gpu| gpu.gradient.strong_count() > 0);
+
+        for gradient in gradients {
+            if self.gradients.contains_key(&gradient.id()) {
+                continue;
+            }
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Gradient Buffer"),
+                contents: bytemuck::bytes_of(&GradientUniform::new(gradient)),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Gradient Bind Group"),
+                layout: &self.layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            let gpu = GpuGradient { gradient: Arc::downgrade(&gradient.0), bind_group };
+            self.gradients.insert(gradient.id(), gpu);
+        }
+    }
+
+    pub(crate) fn bind_group(&self, id: GradientId) -> Option<&wgpu::BindGroup> {
+        self.gradients.get(&id).map(|gpu| &gpu.bind_group)
+    }
+}
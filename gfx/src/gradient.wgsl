@@ -0,0 +1,115 @@
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// The element's transform, composed with those of its ancestors.
+struct TransformUniform {
+    matrix: mat4x4<f32>,
+};
+@group(1) @binding(0)
+var<uniform> transform: TransformUniform;
+
+const MAX_STOPS: u32 = 16u;
+const TAU: f32 = 6.283185307179586;
+
+struct GradientUniform {
+    // 0 linear, 1 radial, 2 conic.
+    kind: u32,
+    stop_count: u32,
+    params: vec4<f32>,
+    offsets: array<vec4<f32>, 4>,
+    colors: array<vec4<f32>, MAX_STOPS>,
+};
+@group(2) @binding(0)
+var<uniform> gradient: GradientUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    // Position inside the element, in logical pixels.
+    @location(2) uv: vec2<f32>,
+};
+
+// One copy of the element, see `InstanceRaw`.
+struct InstanceInput {
+    // The first two columns of the instance's transform, and its translation.
+    @location(3) axes: vec4<f32>,
+    @location(4) offset: vec2<f32>,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) local: vec2<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color * instance.color;
+    out.local = model.uv;
+    let position = transform.matrix * vec4<f32>(model.position, 1.0);
+    let placed = instance.axes.xy * position.x + instance.axes.zw * position.y + instance.offset;
+    out.clip_position = camera.view_proj * vec4<f32>(placed, position.zw);
+    return out;
+}
+
+// Fragment shader
+
+fn offset(i: u32) -> f32 {
+    return gradient.offsets[i / 4u][i % 4u];
+}
+
+fn premultiplied(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// Where `p` falls along the gradient, before clamping.
+fn position(p: vec2<f32>) -> f32 {
+    let params = gradient.params;
+    switch gradient.kind {
+        case 0u: {
+            let d = params.zw - params.xy;
+            return dot(p - params.xy, d) / max(dot(d, d), 1e-6);
+        }
+        case 1u: {
+            return length(p - params.xy) / max(params.z, 1e-6);
+        }
+        default: {
+            let d = p - params.xy;
+            return fract((atan2(d.y, d.x) - params.z) / TAU);
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let count = gradient.stop_count;
+    if count == 0u {
+        return vec4<f32>(0.0);
+    }
+
+    let t = clamp(position(in.local), 0.0, 1.0);
+    // Interpolating pre-multiplied colors keeps transparent stops from
+    // darkening their neighbours.
+    var color = premultiplied(gradient.colors[0]);
+    for (var i = 1u; i < count; i++) {
+        let start = offset(i - 1u);
+        let end = offset(i);
+        if t >= end {
+            color = premultiplied(gradient.colors[i]);
+        } else if t > start {
+            let f = (t - start) / max(end - start, 1e-6);
+            color = mix(premultiplied(gradient.colors[i - 1u]), premultiplied(gradient.colors[i]), f);
+            break;
+        }
+    }
+    // Tinted by instances.
+    return color * premultiplied(in.color);
+}
@@ -0,0 +1,74 @@
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// The element's transform, composed with those of its ancestors.
+struct TransformUniform {
+    matrix: mat4x4<f32>,
+};
+@group(1) @binding(0)
+var<uniform> transform: TransformUniform;
+
+@group(2) @binding(0)
+var t_image: texture_2d<f32>;
+@group(2) @binding(1)
+var s_image: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+// One copy of the element, see `InstanceRaw`.
+struct InstanceInput {
+    // The first two columns of the instance's transform, and its translation.
+    @location(3) axes: vec4<f32>,
+    @location(4) offset: vec2<f32>,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color * instance.color;
+    out.uv = model.uv;
+    let position = transform.matrix * vec4<f32>(model.position, 1.0);
+    let placed = instance.axes.xy * position.x + instance.axes.zw * position.y + instance.offset;
+    out.clip_position = camera.view_proj * vec4<f32>(placed, position.zw);
+    return out;
+}
+
+// Fragment shader
+
+fn tinted(texel: vec4<f32>, tint: vec4<f32>) -> vec4<f32> {
+    let color = texel * tint;
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// Images that do not cover the whole shape leave the rest of it empty.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(t_image, s_image, in.uv);
+    if any(in.uv < vec2<f32>(0.0)) || any(in.uv > vec2<f32>(1.0)) {
+        discard;
+    }
+    return tinted(texel, in.color);
+}
+
+// Tiled images rely on a repeating sampler.
+@fragment
+fn fs_tile(in: VertexOutput) -> @location(0) vec4<f32> {
+    return tinted(textureSample(t_image, s_image, in.uv), in.color);
+}
@@ -0,0 +1,86 @@
+// Instances: copies of an element drawn from the same mesh.
+//
+// Every draw reads a second vertex buffer stepped per instance. Elements
+// without instances are drawn with the identity instance at the start of that
+// buffer, so every pipeline takes the same two buffers.
+
+use crate::transform::Transform;
+
+/// One copy of an instanced element.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instance {
+    /// Applied after the element's own transform, around the top-left corner
+    /// of its rectangle.
+    pub transform: Transform,
+    /// Multiplies the colors of the element, including its alpha.
+    pub color: [f32; 4],
+}
+
+impl Instance {
+    pub fn new(transform: Transform, color: [f32; 4]) -> Instance {
+        Instance { transform, color }
+    }
+
+    /// An untinted copy moved by `(x, y)`.
+    pub fn at(x: f32, y: f32) -> Instance {
+        Instance::new(Transform::translate(x, y), [1.0; 4])
+    }
+}
+
+/// An instance as the vertex shaders read it, with its transform already
+/// moved to the element's corner.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct InstanceRaw {
+    // The first two columns of the transform matrix.
+    axes: [f32; 4],
+    offset: [f32; 2],
+    _padding: [f32; 2],
+    color: [f32; 4],
+}
+
+impl InstanceRaw {
+    pub(crate) const IDENTITY: InstanceRaw = InstanceRaw {
+        axes: [1.0, 0.0, 0.0, 1.0],
+        offset: [0.0; 2],
+        _padding: [0.0; 2],
+        color: [1.0; 4],
+    };
+
+    pub(crate) fn new(instance: &Instance, origin: [f32; 2]) -> InstanceRaw {
+        let [a, b, c, d, e, f] = instance.transform.around(origin).matrix();
+        InstanceRaw { axes: [a, b, c, d], offset: [e, f], _padding: [0.0; 2], color: instance.color }
+    }
+
+    /// The transform of the instance, already placed.
+    pub(crate) fn transform(&self) -> Transform {
+        let [a, b, c, d] = self.axes;
+        let [e, f] = self.offset;
+        Transform::from_matrix([a, b, c, d, e, f])
+    }
+
+    pub(crate) fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            // After the locations of `Vertex`.
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
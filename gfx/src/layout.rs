@@ -0,0 +1,270 @@
+// Flexbox-style layout of element trees.
+//
+// Layout runs in two passes: `measure` works out how much room an element
+// needs from its content, bottom-up, and `arrange` hands out absolute
+// rectangles top-down, growing `Size::Fill` children into the leftover space.
+// All sizes are in logical pixels.
+
+use crate::{shapes::Rect, ui::Element};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Size {
+    /// As large as the content.
+    #[default]
+    Auto,
+    Px(f32),
+    /// A share of the space left over by the siblings, weighted by the value.
+    /// Along the cross axis, and for stacked children, this fills all of it.
+    Fill(f32),
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Column,
+    Row,
+    /// Children are placed on top of each other.
+    Stack,
+}
+
+/// Distribution of free space along the main axis.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+}
+
+/// Placement of children along the cross axis, or along both axes in a stack.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+    Stretch,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Edges {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Edges {
+    pub fn all(value: f32) -> Edges {
+        Edges { top: value, right: value, bottom: value, left: value }
+    }
+
+    pub fn symmetric(horizontal: f32, vertical: f32) -> Edges {
+        Edges { top: vertical, right: horizontal, bottom: vertical, left: horizontal }
+    }
+
+    // Leading and trailing edge along an axis (0 = x, 1 = y).
+    fn along(&self, axis: usize) -> (f32, f32) {
+        if axis == 0 { (self.left, self.right) } else { (self.top, self.bottom) }
+    }
+
+    fn sum(&self) -> [f32; 2] {
+        [self.left + self.right, self.top + self.bottom]
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layout {
+    pub direction: Direction,
+    pub justify: Justify,
+    pub align: Align,
+    /// Space between consecutive children along the main axis.
+    pub gap: f32,
+    pub padding: Edges,
+    pub margin: Edges,
+    pub width: Size,
+    pub height: Size,
+}
+
+impl Layout {
+    fn size(&self, axis: usize) -> Size {
+        if axis == 0 { self.width } else { self.height }
+    }
+}
+
+/// Lays out a top-level element, and everything below it, in a viewport of
+/// the given logical size.
+pub(crate) fn layout_root(element: &mut Element, viewport: [f32; 2]) {
+    let measured = measure(element);
+    let margin = element.layout.margin;
+    let available = [viewport[0] - margin.sum()[0], viewport[1] - margin.sum()[1]];
+    let size = [0, 1].map(|axis| match element.layout.size(axis) {
+        Size::Px(px) => px,
+        Size::Auto => measured[axis],
+        Size::Fill(_) => available[axis].max(0.0),
+    });
+    arrange(element, Rect::new(margin.left, margin.top, size[0], size[1]));
+}
+
+// The size an element asks for, without margins. `Fill` sizes ask for their
+// content; they only grow once the parent knows how much space is left.
+fn measure(element: &Element) -> [f32; 2] {
+    let layout = &element.layout;
+
+    let content = if element.children.is_empty() {
+        // Shapes and text are drawn relative to the element's top-left corner.
+        let shape = element.shape.iter().fold([0.0f32; 2], |size, p| [size[0].max(p[0]), size[1].max(p[1])]);
+        let text = element.text.as_ref().map_or([0.0; 2], |text| text.measure());
+        [shape[0].max(text[0]), shape[1].max(text[1])]
+    } else {
+        let outer: Vec<[f32; 2]> = element
+            .children
+            .iter()
+            .map(|child| {
+                let size = measure(child);
+                let margin = child.layout.margin.sum();
+                [size[0] + margin[0], size[1] + margin[1]]
+            })
+            .collect();
+        let gaps = layout.gap * (outer.len() - 1) as f32;
+        let sum = |axis: usize| outer.iter().map(|size| size[axis]).sum::<f32>() + gaps;
+        let max = |axis: usize| outer.iter().map(|size| size[axis]).fold(0.0, f32::max);
+        let padding = layout.padding.sum();
+        let content = match layout.direction {
+            Direction::Row => [sum(0), max(1)],
+            Direction::Column => [max(0), sum(1)],
+            Direction::Stack => [max(0), max(1)],
+        };
+        [content[0] + padding[0], content[1] + padding[1]]
+    };
+
+    [0, 1].map(|axis| match layout.size(axis) {
+        Size::Px(px) => px,
+        Size::Auto | Size::Fill(_) => content[axis],
+    })
+}
+
+fn arrange(element: &mut Element, rect: Rect) {
+    element.rect = rect;
+    if element.children.is_empty() {
+        return;
+    }
+
+    let layout = element.layout.clone();
+    let padding = layout.padding;
+    let content = Rect::new(
+        rect.x + padding.left,
+        rect.y + padding.top,
+        (rect.width - padding.sum()[0]).max(0.0),
+        (rect.height - padding.sum()[1]).max(0.0),
+    );
+
+    match layout.direction {
+        Direction::Row => flex(&mut element.children, content, &layout, 0),
+        Direction::Column => flex(&mut element.children, content, &layout, 1),
+        Direction::Stack => {
+            for child in &mut element.children {
+                let measured = measure(child);
+                let margin = child.layout.margin;
+                let mut position = [0.0; 2];
+                let mut size = [0.0; 2];
+                for axis in [0, 1] {
+                    let (leading, trailing) = margin.along(axis);
+                    let available = content_size(content, axis) - leading - trailing;
+                    size[axis] = cross_size(child.layout.size(axis), layout.align, measured[axis], available);
+                    position[axis] = content_min(content, axis)
+                        + leading
+                        + align_offset(layout.align, available, size[axis]);
+                }
+                arrange(child, Rect::new(position[0], position[1], size[0], size[1]));
+            }
+        }
+    }
+}
+
+// Places children one after another along `axis`.
+fn flex(children: &mut [Element], content: Rect, layout: &Layout, axis: usize) {
+    let cross = 1 - axis;
+    let count = children.len();
+
+    let measured: Vec<[f32; 2]> = children.iter().map(measure).collect();
+    let mut main: Vec<f32> = Vec::with_capacity(count);
+    let mut weights: Vec<f32> = Vec::with_capacity(count);
+    let mut used = layout.gap * (count - 1) as f32;
+    for (child, measured) in children.iter().zip(&measured) {
+        let (size, weight) = match child.layout.size(axis) {
+            Size::Fill(weight) => (0.0, weight.max(0.0)),
+            _ => (measured[axis], 0.0),
+        };
+        let (leading, trailing) = child.layout.margin.along(axis);
+        used += size + leading + trailing;
+        main.push(size);
+        weights.push(weight);
+    }
+
+    let mut free = content_size(content, axis) - used;
+    let total_weight: f32 = weights.iter().sum();
+    if total_weight > 0.0 {
+        for (size, weight) in main.iter_mut().zip(&weights) {
+            *size += free.max(0.0) * weight / total_weight;
+        }
+        free = 0.0;
+    }
+    let free = free.max(0.0);
+
+    let (mut cursor, spacing) = match layout.justify {
+        Justify::Start => (0.0, layout.gap),
+        Justify::Center => (free / 2.0, layout.gap),
+        Justify::End => (free, layout.gap),
+        Justify::SpaceBetween if count > 1 => (0.0, layout.gap + free / (count - 1) as f32),
+        Justify::SpaceBetween => (0.0, layout.gap),
+        Justify::SpaceAround => (free / count as f32 / 2.0, layout.gap + free / count as f32),
+    };
+
+    for (i, child) in children.iter_mut().enumerate() {
+        let (leading, trailing) = child.layout.margin.along(axis);
+        let (cross_leading, cross_trailing) = child.layout.margin.along(cross);
+        let available = content_size(content, cross) - cross_leading - cross_trailing;
+        let size_cross = cross_size(child.layout.size(cross), layout.align, measured[i][cross], available);
+
+        cursor += leading;
+        let mut position = [0.0; 2];
+        let mut size = [0.0; 2];
+        position[axis] = content_min(content, axis) + cursor;
+        position[cross] = content_min(content, cross)
+            + cross_leading
+            + align_offset(layout.align, available, size_cross);
+        size[axis] = main[i];
+        size[cross] = size_cross;
+        arrange(child, Rect::new(position[0], position[1], size[0], size[1]));
+
+        cursor += main[i] + trailing + spacing;
+    }
+}
+
+fn cross_size(size: Size, align: Align, measured: f32, available: f32) -> f32 {
+    match size {
+        Size::Px(px) => px,
+        Size::Fill(_) => available.max(0.0),
+        Size::Auto if align == Align::Stretch => available.max(0.0),
+        Size::Auto => measured,
+    }
+}
+
+fn align_offset(align: Align, available: f32, size: f32) -> f32 {
+    match align {
+        Align::Start | Align::Stretch => 0.0,
+        Align::Center => (available - size) / 2.0,
+        Align::End => available - size,
+    }
+}
+
+fn content_min(rect: Rect, axis: usize) -> f32 {
+    rect.min()[axis]
+}
+
+fn content_size(rect: Rect, axis: usize) -> f32 {
+    if axis == 0 { rect.width } else { rect.height }
+}
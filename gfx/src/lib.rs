@@ -1,15 +1,68 @@
 use std::sync::Arc;
 
-use winit::{application::ApplicationHandler, event::WindowEvent, event_loop::ActiveEventLoop, window::{Window, WindowId}};
+use winit::{application::ApplicationHandler, event::{MouseScrollDelta, WindowEvent}, event_loop::ActiveEventLoop, window::{Window, WindowId}};
 
-use crate::state::State;
+use crate::{animation::Animator, clock::FrameClock, config::RendererConfig, pointer::{Pointer, LINE_HEIGHT}, scene::Scene, state::State};
 
-mod ui;
-mod state;
+pub mod ui;
+pub mod animation;
+pub mod batch;
+pub mod clock;
+pub mod config;
+pub mod error;
+pub mod gradient;
+pub mod instance;
+pub mod layout;
+pub mod pointer;
+pub mod profiler;
+pub mod scene;
+pub mod shaders;
+pub mod shapes;
+pub mod state;
+pub mod stroke;
+pub mod text;
+pub mod texture;
+pub mod transform;
+mod atlas;
+mod camera;
+mod geometry;
+mod pipeline;
+mod tessellation;
 
 #[derive(Default)]
 pub struct App {
     state: Option<State>,
+    scene: Scene,
+    pointer: Pointer,
+    config: RendererConfig,
+    clock: FrameClock,
+    animator: Animator,
+}
+
+impl App {
+    /// An app whose renderer is created with `config` once the window is.
+    pub fn new(config: RendererConfig) -> App {
+        App { config, ..Default::default() }
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// Elements added here are drawn from the next frame on, whether or not
+    /// the window exists yet.
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// Animations started here advance every frame, before it is rendered.
+    pub fn animator_mut(&mut self) -> &mut Animator {
+        &mut self.animator
+    }
+
+    pub fn clock(&self) -> &FrameClock {
+        &self.clock
+    }
 }
 
 impl ApplicationHandler for App {
@@ -17,33 +70,73 @@ impl ApplicationHandler for App {
         // Create window object
         let window = Arc::new(
             event_loop
-                .create_window(Window::default_attributes().with_transparent(true))
+                .create_window(Window::default_attributes().with_transparent(self.config.is_transparent()))
                 .unwrap(),
         );
 
-        let state = pollster::block_on(State::new(window.clone()));
-        self.state = Some(state);
+        match pollster::block_on(State::new(window.clone(), &self.config)) {
+            Ok(state) => self.state = Some(state),
+            Err(err) => {
+                log::error!("failed to create the renderer: {err:#}");
+                event_loop.exit();
+                return;
+            }
+        }
 
         window.request_redraw();
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
-        let state = self.state.as_mut().unwrap();
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
         match event {
             WindowEvent::CloseRequested => {
                 println!("The close button was pressed; stopping");
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested => {
-                state.render();
-                // Emits a new redraw requested event.
-                state.get_window().request_redraw();
+                let delta = self.clock.tick();
+                self.animator.update(&mut self.scene, delta);
+                if let Err(err) = state.render(&mut self.scene) {
+                    log::error!("{err}");
+                    event_loop.exit();
+                    return;
+                }
+                // Emits a new redraw requested event. A paused state waits
+                // for the resize that brings the window back.
+                if let Some(window) = state.get_window().filter(|_| !state.is_paused()) {
+                    window.request_redraw();
+                }
             }
             WindowEvent::Resized(size) => {
                 // Reconfigures the size of the surface. We do not re-render
                 // here as this event is always followed up by redraw request.
                 state.resize(size);
             }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = position.to_logical::<f32>(state.scale_factor());
+                self.pointer.moved(&mut self.scene, [position.x, position.y]);
+            }
+            WindowEvent::CursorLeft { .. } => self.pointer.left(&mut self.scene),
+            WindowEvent::MouseInput { state: button_state, button, .. } => {
+                self.pointer.button(&mut self.scene, button, button_state.is_pressed());
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [x * LINE_HEIGHT, y * LINE_HEIGHT],
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let position = position.to_logical::<f32>(state.scale_factor());
+                        [position.x, position.y]
+                    }
+                };
+                self.pointer.scroll(&mut self.scene, delta);
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                // Element coordinates are logical pixels, so the projection
+                // has to follow the new scale. A resize event comes next.
+                state.set_scale_factor(scale_factor);
+            }
             _ => (),
         }
     }
@@ -0,0 +1,282 @@
+// Shared setup for the render pipelines. They all draw `Vertex` triangle
+// lists, once per `InstanceRaw`, into the same target and only differ in
+// shader, bind groups and blending.
+//
+// When masks are enabled every pipeline also tests the stencil buffer, which
+// holds how many masks cover each sample: draws only land where it equals the
+// number of masks they are inside. The mask pipelines draw shapes into the
+// stencil buffer alone, counting them in before the masked draws and out
+// afterwards.
+
+use crate::{
+    instance::InstanceRaw,
+    shaders::{self, ShaderKind},
+    state::Vertex,
+    ui::MaskOp,
+};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+// Also where masks are kept. Depth is only tested when the renderer was
+// configured with a depth buffer.
+const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+pub(crate) struct PipelineDesc<'a> {
+    pub label: &'a str,
+    pub layout: &'a wgpu::PipelineLayout,
+    pub shader: &'a wgpu::ShaderModule,
+    pub vertex_entry: &'a str,
+    pub fragment_entry: &'a str,
+    pub format: wgpu::TextureFormat,
+    pub blend: wgpu::BlendState,
+    pub sample_count: u32,
+    pub depth_format: Option<wgpu::TextureFormat>,
+    pub depth_test: bool,
+    /// Set for pipelines that draw masks instead of colors.
+    pub mask: Option<MaskOp>,
+}
+
+pub(crate) fn create_pipeline(device: &wgpu::Device, desc: &PipelineDesc) -> wgpu::RenderPipeline {
+    let (write_mask, pass_op) = match desc.mask {
+        None => (wgpu::ColorWrites::ALL, wgpu::StencilOperation::Keep),
+        Some(MaskOp::Push) => (wgpu::ColorWrites::empty(), wgpu::StencilOperation::IncrementClamp),
+        Some(MaskOp::Pop) => (wgpu::ColorWrites::empty(), wgpu::StencilOperation::DecrementClamp),
+    };
+    // Overlapping triangles of a mask only count once, since the first one
+    // already changes what the others expect.
+    let stencil_face = wgpu::StencilFaceState {
+        compare: wgpu::CompareFunction::Equal,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op,
+    };
+    // Masks are not hidden behind anything.
+    let depth_test = desc.depth_test && desc.mask.is_none();
+    let stencil = match desc.depth_format {
+        Some(format) if format.has_stencil_aspect() => wgpu::StencilState {
+            front: stencil_face,
+            back: stencil_face,
+            read_mask: !0,
+            write_mask: !0,
+        },
+        _ => wgpu::StencilState::default(),
+    };
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor { 
+        label: Some(desc.label), 
+        layout: Some(desc.layout), 
+        vertex: wgpu::VertexState {
+            module: desc.shader,
+            entry_point: Some(desc.vertex_entry),
+            buffers: &[Vertex::desc(), InstanceRaw::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }, 
+        fragment: Some(wgpu::FragmentState { 
+            module: desc.shader, 
+            entry_point: Some(desc.fragment_entry), 
+            targets: &[Some(wgpu::ColorTargetState {
+                format: desc.format,
+                blend: Some(desc.blend),
+                write_mask,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(), 
+        }), 
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            // The projection flips y, so winding is not a reliable way to
+            // tell front from back; 2D geometry is never seen from behind.
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        }, 
+        depth_stencil: desc.depth_format.map(|format| wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: depth_test,
+            // Larger z is in front, and later draws win ties so children
+            // still cover their parents.
+            depth_compare: if depth_test { wgpu::CompareFunction::GreaterEqual } else { wgpu::CompareFunction::Always },
+            stencil,
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: desc.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        }, 
+        multiview: None, 
+        cache: None, 
+    })
+}
+
+/// What every pipeline renders into.
+#[derive(Copy, Clone)]
+pub(crate) struct PipelineTarget {
+    pub format: wgpu::TextureFormat,
+    pub sample_count: u32,
+    pub depth_test: bool,
+    pub masks: bool,
+}
+
+impl PipelineTarget {
+    /// The format of the depth and stencil attachment, `None` when neither
+    /// is used.
+    pub(crate) fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        match (self.depth_test, self.masks) {
+            (_, true) => Some(DEPTH_STENCIL_FORMAT),
+            (true, false) => Some(DEPTH_FORMAT),
+            (false, false) => None,
+        }
+    }
+}
+
+/// The bind group layouts the shaders use: the camera at group 0, the
+/// transform of the draw at group 1, and the material at group 2.
+pub(crate) struct BindGroupLayouts<'a> {
+    pub camera: &'a wgpu::BindGroupLayout,
+    pub transform: &'a wgpu::BindGroupLayout,
+    pub atlas: &'a wgpu::BindGroupLayout,
+    pub image: &'a wgpu::BindGroupLayout,
+    pub gradient: &'a wgpu::BindGroupLayout,
+}
+
+struct PipelineLayouts {
+    color: wgpu::PipelineLayout,
+    text: wgpu::PipelineLayout,
+    image: wgpu::PipelineLayout,
+    gradient: wgpu::PipelineLayout,
+}
+
+/// One pipeline per material, built from the shaders in [`ShaderKind`], and
+/// two for masks, built from the color shader.
+pub(crate) struct Pipelines {
+    target: PipelineTarget,
+    layouts: PipelineLayouts,
+    pub color: wgpu::RenderPipeline,
+    pub mask_push: wgpu::RenderPipeline,
+    pub mask_pop: wgpu::RenderPipeline,
+    pub text: wgpu::RenderPipeline,
+    pub image: wgpu::RenderPipeline,
+    pub tiled_image: wgpu::RenderPipeline,
+    pub gradient: wgpu::RenderPipeline,
+}
+
+impl Pipelines {
+    /// Builds every pipeline from the shaders compiled into the crate.
+    pub(crate) fn new(device: &wgpu::Device, target: PipelineTarget, bind_groups: &BindGroupLayouts) -> Pipelines {
+        let layout = |label, bind_group_layouts: &[&wgpu::BindGroupLayout]| {
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts,
+                push_constant_ranges: &[],
+            })
+        };
+        let layouts = PipelineLayouts {
+            color: layout("Render Pipeline Layout", &[bind_groups.camera, bind_groups.transform]),
+            text: layout("Text Pipeline Layout", &[bind_groups.camera, bind_groups.transform, bind_groups.atlas]),
+            image: layout("Image Pipeline Layout", &[bind_groups.camera, bind_groups.transform, bind_groups.image]),
+            gradient: layout(
+                "Gradient Pipeline Layout",
+                &[bind_groups.camera, bind_groups.transform, bind_groups.gradient],
+            ),
+        };
+
+        let module = |kind: ShaderKind| {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(kind.file_name()),
+                source: wgpu::ShaderSource::Wgsl(kind.builtin().into()),
+            })
+        };
+        let color_shader = module(ShaderKind::Color);
+        let text_shader = module(ShaderKind::Text);
+        let image_shader = module(ShaderKind::Image);
+        let gradient_shader = module(ShaderKind::Gradient);
+
+        let create = |label, layout, shader, fragment_entry| create_for(device, &target, label, layout, shader, fragment_entry, None);
+        let mask = |label, op| create_for(device, &target, label, &layouts.color, &color_shader, "fs_main", Some(op));
+        Pipelines {
+            color: create("Render Pipeline", &layouts.color, &color_shader, "fs_main"),
+            mask_push: mask("Mask Push Pipeline", MaskOp::Push),
+            mask_pop: mask("Mask Pop Pipeline", MaskOp::Pop),
+            text: create("Text Pipeline", &layouts.text, &text_shader, "fs_main"),
+            image: create("Image Pipeline", &layouts.image, &image_shader, "fs_main"),
+            tiled_image: create("Tiled Image Pipeline", &layouts.image, &image_shader, "fs_tile"),
+            gradient: create("Gradient Pipeline", &layouts.gradient, &gradient_shader, "fs_main"),
+            target,
+            layouts,
+        }
+    }
+
+    /// Rebuilds the pipelines that use the shader `kind` from `source`. If
+    /// the source does not validate, or the pipelines cannot be created from
+    /// it, the current ones are kept and the diagnostic is returned.
+    pub(crate) fn replace(&mut self, device: &wgpu::Device, kind: ShaderKind, source: &str) -> anyhow::Result<()> {
+        // wgpu treats invalid shaders as fatal, so naga gets to see them
+        // first.
+        shaders::validate(source)?;
+
+        // Catches what naga cannot know about, such as entry points or
+        // bindings that do not match the layout.
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(kind.file_name()),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let create = |label, layout, fragment_entry| create_for(device, &self.target, label, layout, &shader, fragment_entry, None);
+        let mask = |label, op| create_for(device, &self.target, label, &self.layouts.color, &shader, "fs_main", Some(op));
+        let mut masks = None;
+        let (pipeline, tiled_pipeline) = match kind {
+            ShaderKind::Color => {
+                masks = Some((mask("Mask Push Pipeline", MaskOp::Push), mask("Mask Pop Pipeline", MaskOp::Pop)));
+                (create("Render Pipeline", &self.layouts.color, "fs_main"), None)
+            }
+            ShaderKind::Text => (create("Text Pipeline", &self.layouts.text, "fs_main"), None),
+            ShaderKind::Image => (
+                create("Image Pipeline", &self.layouts.image, "fs_main"),
+                Some(create("Tiled Image Pipeline", &self.layouts.image, "fs_tile")),
+            ),
+            ShaderKind::Gradient => (create("Gradient Pipeline", &self.layouts.gradient, "fs_main"), None),
+        };
+        if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("{err}");
+        }
+
+        match kind {
+            ShaderKind::Color => {
+                self.color = pipeline;
+                (self.mask_push, self.mask_pop) = masks.expect("the color shader also draws masks");
+            }
+            ShaderKind::Text => self.text = pipeline,
+            ShaderKind::Image => {
+                self.image = pipeline;
+                self.tiled_image = tiled_pipeline.expect("the image shader has two pipelines");
+            }
+            ShaderKind::Gradient => self.gradient = pipeline,
+        }
+        Ok(())
+    }
+}
+
+fn create_for(
+    device: &wgpu::Device,
+    target: &PipelineTarget,
+    label: &str,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_entry: &str,
+    mask: Option<MaskOp>,
+) -> wgpu::RenderPipeline {
+    create_pipeline(device, &PipelineDesc {
+        label,
+        layout,
+        shader,
+        vertex_entry: "vs_main",
+        fragment_entry,
+        format: target.format,
+        blend: wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        sample_count: target.sample_count,
+        depth_format: target.depth_format(),
+        depth_test: target.depth_test,
+        mask,
+    })
+}
@@ -0,0 +1,170 @@
+// Routing of pointer input to the elements of a scene.
+//
+// Every event is hit-tested against the scene, topmost element first, to find
+// the innermost element under the cursor. Hover follows the whole chain from
+// that element up to its root, like CSS `:hover`; presses, releases, clicks
+// and scrolls bubble up the chain to the first element with a handler for
+// them.
+
+use std::collections::HashMap;
+
+use winit::event::MouseButton;
+
+use crate::{
+    scene::{ElementId, Scene},
+    ui::Element,
+};
+
+/// Logical pixels scrolled per line for wheels that report lines.
+pub const LINE_HEIGHT: f32 = 20.0;
+
+/// What handlers are told about the pointer. Positions are in logical pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PointerEvent {
+    pub position: [f32; 2],
+    /// The button pressed or released, if any.
+    pub button: Option<MouseButton>,
+    /// Scroll distance in logical pixels, zero for anything but scrolling.
+    pub scroll: [f32; 2],
+}
+
+type Handler = Box<dyn FnMut(&PointerEvent)>;
+
+#[derive(Default)]
+pub(crate) struct Handlers {
+    pub hover: Option<Box<dyn FnMut(bool)>>,
+    pub press: Option<Handler>,
+    pub release: Option<Handler>,
+    pub click: Option<Handler>,
+    pub scroll: Option<Handler>,
+}
+
+// An element inside a scene: a top-level element and the child indices
+// leading down to it.
+#[derive(Clone, Debug, PartialEq)]
+struct Target {
+    id: ElementId,
+    path: Vec<usize>,
+}
+
+impl Target {
+    // The element and all its ancestors, innermost first.
+    fn chain(&self) -> impl Iterator<Item = Target> + '_ {
+        (0..=self.path.len()).rev().map(|depth| Target { id: self.id, path: self.path[..depth].to_vec() })
+    }
+
+    fn element_mut<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut Element> {
+        scene.get_mut_untracked(self.id)?.descendant_mut(&self.path)
+    }
+}
+
+/// Tracks the cursor and delivers pointer events to element handlers.
+#[derive(Default)]
+pub struct Pointer {
+    position: Option<[f32; 2]>,
+    hovered: Option<Target>,
+    // Where each held button went down, to recognize clicks.
+    pressed: HashMap<MouseButton, Target>,
+}
+
+impl Pointer {
+    pub fn new() -> Pointer {
+        Pointer::default()
+    }
+
+    /// Last known cursor position, `None` while it is outside the window.
+    pub fn position(&self) -> Option<[f32; 2]> {
+        self.position
+    }
+
+    pub fn moved(&mut self, scene: &mut Scene, position: [f32; 2]) {
+        self.position = Some(position);
+        let hovered = scene.hit_path(position).map(|(id, path)| Target { id, path });
+        self.set_hovered(scene, hovered);
+    }
+
+    /// The cursor left the window.
+    pub fn left(&mut self, scene: &mut Scene) {
+        self.position = None;
+        self.set_hovered(scene, None);
+    }
+
+    pub fn button(&mut self, scene: &mut Scene, button: MouseButton, pressed: bool) {
+        let Some(position) = self.position else {
+            return;
+        };
+        let event = PointerEvent { position, button: Some(button), scroll: [0.0, 0.0] };
+        let target = scene.hit_path(position).map(|(id, path)| Target { id, path });
+
+        if pressed {
+            if let Some(target) = target {
+                dispatch(scene, &target, &event, |handlers| &mut handlers.press);
+                self.pressed.insert(button, target);
+            }
+            return;
+        }
+
+        if let Some(target) = &target {
+            dispatch(scene, target, &event, |handlers| &mut handlers.release);
+        }
+        // A click needs the release to land on the element, or one of its
+        // descendants, that the press went to.
+        if let (Some(down), Some(up)) = (self.pressed.remove(&button), target) {
+            let common = down.path.iter().zip(&up.path).take_while(|(a, b)| a == b).count();
+            if down.id == up.id {
+                let target = Target { id: down.id, path: down.path[..common].to_vec() };
+                dispatch(scene, &target, &event, |handlers| &mut handlers.click);
+            }
+        }
+    }
+
+    pub fn scroll(&mut self, scene: &mut Scene, delta: [f32; 2]) {
+        let Some(position) = self.position else {
+            return;
+        };
+        if let Some((id, path)) = scene.hit_path(position) {
+            let event = PointerEvent { position, button: None, scroll: delta };
+            dispatch(scene, &Target { id, path }, &event, |handlers| &mut handlers.scroll);
+        }
+    }
+
+    fn set_hovered(&mut self, scene: &mut Scene, hovered: Option<Target>) {
+        if hovered == self.hovered {
+            return;
+        }
+        let old: Vec<Target> = self.hovered.iter().flat_map(Target::chain).collect();
+        let new: Vec<Target> = hovered.iter().flat_map(Target::chain).collect();
+
+        for target in old.iter().filter(|target| !new.contains(target)) {
+            notify_hover(scene, target, false);
+        }
+        for target in new.iter().rev().filter(|target| !old.contains(target)) {
+            notify_hover(scene, target, true);
+        }
+        self.hovered = hovered;
+    }
+}
+
+fn notify_hover(scene: &mut Scene, target: &Target, hovered: bool) {
+    if let Some(handler) = target.element_mut(scene).and_then(|element| element.handlers.hover.as_mut()) {
+        handler(hovered);
+    }
+}
+
+// Calls the innermost handler picked by `slot` along the chain of `target`.
+fn dispatch(
+    scene: &mut Scene,
+    target: &Target,
+    event: &PointerEvent,
+    slot: impl Fn(&mut Handlers) -> &mut Option<Handler>,
+) {
+    for target in target.chain() {
+        let Some(element) = target.element_mut(scene) else {
+            continue;
+        };
+        if let Some(handler) = slot(&mut element.handlers) {
+            handler(event);
+            return;
+        }
+    }
+}
@@ -0,0 +1,330 @@
+// Frame timing on the CPU and, where the adapter supports timestamp queries,
+// on the GPU.
+//
+// The CPU side measures how long `State::render` takes and how much time
+// passes between frames. On the GPU every pass writes a timestamp when it
+// begins and when it ends. Those are resolved into a buffer and read back
+// asynchronously a few frames later, so measuring never stalls rendering;
+// frames that find every readback buffer still in use are not timed on the
+// GPU.
+
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    layout::{Direction, Edges, Size},
+    shapes::Rect,
+    ui::Element,
+};
+
+/// How many of the most recent frames the statistics cover.
+pub const STATS_FRAMES: usize = 120;
+
+/// The passes of a frame, in the order they are recorded.
+pub const PASSES: [&str; 2] = ["scene", "overlay"];
+
+// Frames whose timestamps can be on their way back at once.
+const READBACK_BUFFERS: usize = 3;
+const QUERY_COUNT: u32 = 2 * PASSES.len() as u32;
+
+/// Statistics of one timing over the last frames.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub last: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+}
+
+/// The GPU time of one pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassTiming {
+    /// One of [`PASSES`].
+    pub label: &'static str,
+    pub gpu: Summary,
+}
+
+/// How long the last frames took, over at most [`STATS_FRAMES`] frames.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameTimings {
+    /// Frames measured on the CPU.
+    pub frames: usize,
+    /// Time spent in `State::render`, from building the scene to presenting,
+    /// leaving out building the profiler overlay.
+    pub cpu: Option<Summary>,
+    /// Time from the start of one frame to the start of the next.
+    pub interval: Option<Summary>,
+    /// Frames measured on the GPU. Results arrive a few frames late.
+    pub gpu_frames: usize,
+    /// GPU time of all passes of a frame together. `None` while there are
+    /// no results, and always without timestamp queries.
+    pub gpu: Option<Summary>,
+    /// GPU time of each pass that ran, in order.
+    pub passes: Vec<PassTiming>,
+}
+
+// The last `STATS_FRAMES` samples of a timing.
+#[derive(Default)]
+struct Rolling {
+    samples: VecDeque<Duration>,
+}
+
+impl Rolling {
+    fn push(&mut self, sample: Duration) {
+        if self.samples.len() == STATS_FRAMES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    fn summary(&self) -> Option<Summary> {
+        let last = *self.samples.back()?;
+        Some(Summary {
+            last,
+            min: *self.samples.iter().min()?,
+            max: *self.samples.iter().max()?,
+            mean: self.samples.iter().sum::<Duration>() / self.samples.len() as u32,
+        })
+    }
+}
+
+enum Readback {
+    Free,
+    // Resolved into by the frame being recorded, for `passes` passes.
+    Recording { passes: usize },
+    Mapping { passes: usize, mapped: Receiver<Result<(), wgpu::BufferAsyncError>> },
+}
+
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readbacks: Vec<(wgpu::Buffer, Readback)>,
+    // The readback buffer of the frame being recorded.
+    current: Option<usize>,
+    // Nanoseconds per timestamp tick.
+    period: f32,
+}
+
+impl GpuTimer {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> GpuTimer {
+        let size = QUERY_COUNT as u64 * wgpu::QUERY_SIZE as u64;
+        let buffer = |label: &str, usage: wgpu::BufferUsages| {
+            device.create_buffer(&wgpu::BufferDescriptor { label: Some(label), size, usage, mapped_at_creation: false })
+        };
+        GpuTimer {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Timestamp Queries"),
+                ty: wgpu::QueryType::Timestamp,
+                count: QUERY_COUNT,
+            }),
+            resolve: buffer("Timestamp Resolve Buffer", wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC),
+            readbacks: (0..READBACK_BUFFERS)
+                .map(|_| {
+                    let usage = wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST;
+                    (buffer("Timestamp Readback Buffer", usage), Readback::Free)
+                })
+                .collect(),
+            current: None,
+            period: queue.get_timestamp_period(),
+        }
+    }
+}
+
+/// Collects the timings of every frame a state renders.
+pub(crate) struct Profiler {
+    cpu: Rolling,
+    interval: Rolling,
+    gpu: Rolling,
+    passes: [Rolling; PASSES.len()],
+    // When the frame being rendered started.
+    frame_start: Option<Instant>,
+    // Time of the frame that does not count towards its CPU time.
+    excluded: Duration,
+    // `None` without timestamp queries.
+    gpu_timer: Option<GpuTimer>,
+}
+
+impl Profiler {
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Profiler {
+        let timestamps = device.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+        Profiler {
+            cpu: Rolling::default(),
+            interval: Rolling::default(),
+            gpu: Rolling::default(),
+            passes: Default::default(),
+            frame_start: None,
+            excluded: Duration::ZERO,
+            gpu_timer: timestamps.then(|| GpuTimer::new(device, queue)),
+        }
+    }
+
+    pub(crate) fn gpu_supported(&self) -> bool {
+        self.gpu_timer.is_some()
+    }
+
+    /// Starts timing a frame, picking up the GPU results that arrived since
+    /// the last one. Every frame begun has to reach `end_frame`, so call it
+    /// once nothing can skip the frame any more.
+    pub(crate) fn begin_frame(&mut self, device: &wgpu::Device) {
+        let now = Instant::now();
+        if let Some(start) = self.frame_start.replace(now) {
+            self.interval.push(now - start);
+        }
+        self.excluded = Duration::ZERO;
+        self.collect(device, false);
+        if let Some(timer) = &mut self.gpu_timer {
+            // A frame that failed before submitting never used its buffer.
+            for (_, readback) in &mut timer.readbacks {
+                if let Readback::Recording { .. } = readback {
+                    *readback = Readback::Free;
+                }
+            }
+            timer.current = timer.readbacks.iter().position(|(_, readback)| matches!(readback, Readback::Free));
+        }
+    }
+
+    /// Where pass `index` of [`PASSES`] writes its timestamps, if the frame
+    /// is timed on the GPU.
+    pub(crate) fn timestamp_writes(&self, index: usize) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let timer = self.gpu_timer.as_ref().filter(|timer| timer.current.is_some())?;
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &timer.query_set,
+            beginning_of_pass_write_index: Some(2 * index as u32),
+            end_of_pass_write_index: Some(2 * index as u32 + 1),
+        })
+    }
+
+    /// Copies the timestamps of the first `passes` passes towards the CPU,
+    /// after they are recorded into `encoder`.
+    pub(crate) fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder, passes: usize) {
+        let Some(timer) = &mut self.gpu_timer else {
+            return;
+        };
+        let Some(current) = timer.current else {
+            return;
+        };
+        let queries = 2 * passes as u32;
+        let size = queries as u64 * wgpu::QUERY_SIZE as u64;
+        encoder.resolve_query_set(&timer.query_set, 0..queries, &timer.resolve, 0);
+        let (buffer, readback) = &mut timer.readbacks[current];
+        encoder.copy_buffer_to_buffer(&timer.resolve, 0, buffer, 0, size);
+        *readback = Readback::Recording { passes };
+    }
+
+    /// Leaves `time` out of the CPU time of the frame, so measuring does not
+    /// show up in the measurements.
+    pub(crate) fn exclude(&mut self, time: Duration) {
+        self.excluded += time;
+    }
+
+    /// Finishes timing the frame once its commands are submitted.
+    pub(crate) fn end_frame(&mut self) {
+        if let Some(start) = self.frame_start {
+            self.cpu.push(start.elapsed().saturating_sub(self.excluded));
+        }
+        let Some(timer) = &mut self.gpu_timer else {
+            return;
+        };
+        let Some((buffer, readback)) = timer.current.take().map(|current| &mut timer.readbacks[current]) else {
+            return;
+        };
+        // Frames that were skipped before resolving keep their buffer.
+        let Readback::Recording { passes } = *readback else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        *readback = Readback::Mapping { passes, mapped: receiver };
+    }
+
+    /// Reads back the GPU timestamps that are ready, or all of them when
+    /// `wait`ing for the GPU.
+    pub(crate) fn collect(&mut self, device: &wgpu::Device, wait: bool) {
+        let Some(timer) = &mut self.gpu_timer else {
+            return;
+        };
+        let _ = device.poll(if wait { wgpu::PollType::Wait } else { wgpu::PollType::Poll });
+
+        for (buffer, readback) in &mut timer.readbacks {
+            let Readback::Mapping { passes, mapped } = readback else {
+                continue;
+            };
+            match mapped.try_recv() {
+                Err(TryRecvError::Empty) => continue,
+                Ok(Ok(())) => {
+                    let passes = *passes;
+                    let data = buffer.slice(..).get_mapped_range();
+                    let timestamps: &[u64] = bytemuck::cast_slice(&data[..]);
+                    let mut total = Duration::ZERO;
+                    for (pass, pair) in timestamps.chunks_exact(2).take(passes).enumerate() {
+                        let ticks = pair[1].saturating_sub(pair[0]);
+                        let time = Duration::from_nanos((ticks as f64 * timer.period as f64) as u64);
+                        self.passes[pass].push(time);
+                        total += time;
+                    }
+                    self.gpu.push(total);
+                    drop(data);
+                    buffer.unmap();
+                }
+                // Lost along with the device.
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => {}
+            }
+            *readback = Readback::Free;
+        }
+    }
+
+    pub(crate) fn timings(&self) -> FrameTimings {
+        FrameTimings {
+            frames: self.cpu.samples.len(),
+            cpu: self.cpu.summary(),
+            interval: self.interval.summary(),
+            gpu_frames: self.gpu.samples.len(),
+            gpu: self.gpu.summary(),
+            passes: PASSES
+                .iter()
+                .zip(&self.passes)
+                .filter_map(|(&label, rolling)| Some(PassTiming { label, gpu: rolling.summary()? }))
+                .collect(),
+        }
+    }
+
+    /// A graph of the CPU time of the last frames, with their GPU time in
+    /// front, and a line at 60 frames per second.
+    pub(crate) fn overlay(&self) -> Element {
+        const BAR_WIDTH: f32 = 2.0;
+        const HEIGHT: f32 = 80.0;
+        // Pixels per millisecond, fitting 30 frames per second.
+        const SCALE: f32 = HEIGHT / 33.3;
+        let width = BAR_WIDTH * STATS_FRAMES as f32;
+
+        let bar = |x: f32, width: f32, time: Duration, color: [f32; 4]| {
+            let height = (time.as_secs_f32() * 1000.0 * SCALE).min(HEIGHT);
+            Element::new().with_color(color).with_rect(Rect::new(x, HEIGHT - height, width, height))
+        };
+        // The newest frames are on the right.
+        let bars = |rolling: &Rolling, bar_width: f32, color: [f32; 4]| -> Vec<Element> {
+            let start = (STATS_FRAMES - rolling.samples.len()) as f32 * BAR_WIDTH;
+            rolling
+                .samples
+                .iter()
+                .enumerate()
+                .map(|(i, &time)| bar(start + i as f32 * BAR_WIDTH, bar_width, time, color))
+                .collect()
+        };
+        let frame_60 = HEIGHT - 16.667 * SCALE;
+
+        Element::new()
+            .with_direction(Direction::Stack)
+            .with_size(Size::Px(width), Size::Px(HEIGHT))
+            .with_margin(Edges::all(8.0))
+            .with_color([0.0, 0.0, 0.0, 0.6])
+            .with_children(bars(&self.cpu, BAR_WIDTH, [0.2, 0.8, 0.3, 0.9]))
+            .with_children(bars(&self.gpu, BAR_WIDTH / 2.0, [1.0, 0.6, 0.1, 0.9]))
+            .with_child(Element::new().with_color([1.0, 1.0, 1.0, 0.5]).with_rect(Rect::new(0.0, frame_60, width, 1.0)))
+    }
+}
@@ -0,0 +1,181 @@
+// The set of elements drawn every frame.
+//
+// The scene only tracks which elements were added, changed or removed since
+// the last frame; `State` uses that to re-tessellate and re-upload just those
+// elements.
+
+use std::collections::HashMap;
+
+use crate::{instance::Instance, transform::Transform, ui::Element};
+
+/// Handle to an element added to a [`Scene`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ElementId(u64);
+
+struct Entry {
+    element: Element,
+    dirty: bool,
+    // Only the transform or the instances changed.
+    moved: bool,
+}
+
+#[derive(Default)]
+pub struct Scene {
+    next_id: u64,
+    // Draw order, back to front: by z-index, then by insertion.
+    order: Vec<ElementId>,
+    entries: HashMap<ElementId, Entry>,
+    removed: Vec<ElementId>,
+}
+
+pub(crate) struct SceneChanges {
+    pub removed: Vec<ElementId>,
+    pub dirty: Vec<ElementId>,
+    /// Elements that are not dirty, but need their transforms updated.
+    pub moved: Vec<ElementId>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    /// Adds an element on top of everything already in the scene with the
+    /// same or a lower z-index.
+    pub fn add(&mut self, element: Element) -> ElementId {
+        let id = ElementId(self.next_id);
+        self.next_id += 1;
+        let z_index = element.z_index();
+        let position = self.order.partition_point(|other| self.entries[other].element.z_index() <= z_index);
+        self.order.insert(position, id);
+        self.entries.insert(id, Entry { element, dirty: true, moved: false });
+        id
+    }
+
+    pub fn remove(&mut self, id: ElementId) -> Option<Element> {
+        let entry = self.entries.remove(&id)?;
+        self.order.retain(|other| *other != id);
+        self.removed.push(id);
+        Some(entry.element)
+    }
+
+    pub fn clear(&mut self) {
+        self.removed.extend(self.order.drain(..));
+        self.entries.clear();
+    }
+
+    pub fn get(&self, id: ElementId) -> Option<&Element> {
+        self.entries.get(&id).map(|entry| &entry.element)
+    }
+
+    /// Mutable access to an element. The element is re-tessellated and
+    /// uploaded again on the next frame.
+    pub fn get_mut(&mut self, id: ElementId) -> Option<&mut Element> {
+        let entry = self.entries.get_mut(&id)?;
+        entry.dirty = true;
+        Some(&mut entry.element)
+    }
+
+    /// Sets the transform of an element without tessellating it again.
+    /// Returns whether the element is in the scene.
+    pub fn set_transform(&mut self, id: ElementId, transform: Transform) -> bool {
+        let Some(entry) = self.entries.get_mut(&id) else {
+            return false;
+        };
+        entry.element.set_transform(transform);
+        entry.moved = true;
+        true
+    }
+
+    /// Sets the instances of an element without tessellating it again.
+    /// Returns whether the element is in the scene.
+    pub fn set_instances(&mut self, id: ElementId, instances: Option<Vec<Instance>>) -> bool {
+        let Some(entry) = self.entries.get_mut(&id) else {
+            return false;
+        };
+        entry.element.set_instances(instances);
+        entry.moved = true;
+        true
+    }
+
+    pub fn contains(&self, id: ElementId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Element ids in draw order, back to front.
+    pub fn ids(&self) -> impl Iterator<Item = ElementId> + '_ {
+        self.order.iter().copied()
+    }
+
+    /// The topmost element under `point`, in logical pixels. Uses the layout
+    /// of the last rendered frame.
+    pub fn hit_test(&self, point: [f32; 2]) -> Option<ElementId> {
+        self.hit_path(point).map(|(id, _)| id)
+    }
+
+    pub(crate) fn hit_path(&self, point: [f32; 2]) -> Option<(ElementId, Vec<usize>)> {
+        self.order.iter().rev().find_map(|&id| {
+            let element = &self.entries[&id].element;
+            let path = match element.instances() {
+                None => element.hit_path(point, Transform::IDENTITY)?,
+                // Later instances are drawn on top.
+                Some(instances) => instances.iter().rev().find_map(|instance| {
+                    let placed = instance.transform.around(element.rect().min());
+                    element.hit_path(point, placed)
+                })?,
+            };
+            Some((id, path))
+        })
+    }
+
+    // Mutable access that leaves the element's geometry alone, for calling
+    // its event handlers.
+    pub(crate) fn get_mut_untracked(&mut self, id: ElementId) -> Option<&mut Element> {
+        self.entries.get_mut(&id).map(|entry| &mut entry.element)
+    }
+
+    // Lays out an element for the viewport and hands it out for building.
+    pub(crate) fn layout(&mut self, id: ElementId, viewport: [f32; 2]) -> Option<&Element> {
+        let entry = self.entries.get_mut(&id)?;
+        entry.element.compute_layout(viewport);
+        Some(&entry.element)
+    }
+
+    pub(crate) fn mark_all_dirty(&mut self) {
+        for entry in self.entries.values_mut() {
+            entry.dirty = true;
+        }
+    }
+
+    pub(crate) fn take_changes(&mut self) -> SceneChanges {
+        // Changed elements may have a new z-index.
+        let entries = &self.entries;
+        self.order.sort_by_key(|id| entries[id].element.z_index());
+
+        let mut dirty = Vec::new();
+        let mut moved = Vec::new();
+        for id in &self.order {
+            let entry = self.entries.get_mut(id).unwrap();
+            if std::mem::replace(&mut entry.dirty, false) {
+                dirty.push(*id);
+            } else if entry.moved {
+                moved.push(*id);
+            }
+            entry.moved = false;
+        }
+
+        SceneChanges {
+            removed: std::mem::take(&mut self.removed),
+            dirty,
+            moved,
+        }
+    }
+}
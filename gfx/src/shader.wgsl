@@ -1,8 +1,29 @@
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// The element's transform, composed with those of its ancestors.
+struct TransformUniform {
+    matrix: mat4x4<f32>,
+};
+@group(1) @binding(0)
+var<uniform> transform: TransformUniform;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec4<f32>,
 };
 
+// One copy of the element, see `InstanceRaw`.
+struct InstanceInput {
+    // The first two columns of the instance's transform, and its translation.
+    @location(3) axes: vec4<f32>,
+    @location(4) offset: vec2<f32>,
+    @location(5) color: vec4<f32>,
+};
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
@@ -11,10 +32,13 @@ struct VertexOutput {
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.color = model.color;
-    out.clip_position = vec4<f32>(model.position, 1.0);
+    out.color = model.color * instance.color;
+    let position = transform.matrix * vec4<f32>(model.position, 1.0);
+    let placed = instance.axes.xy * position.x + instance.axes.zw * position.y + instance.offset;
+    out.clip_position = camera.view_proj * vec4<f32>(placed, position.zw);
     return out;
 }
 
@@ -22,5 +46,6 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color);
+    // Colors are given straight, but blended pre-multiplied.
+    return vec4<f32>(in.color.rgb * in.color.a, in.color.a);
 }
\ No newline at end of file
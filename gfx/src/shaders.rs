@@ -0,0 +1,117 @@
+// The WGSL shaders of the render pipelines, and reloading them from disk.
+//
+// The shaders are compiled into the crate. When the renderer is given a
+// shader directory, files there named like the built-in shaders replace them,
+// and saving one rebuilds the pipelines that use it on the next frame.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use anyhow::Context;
+use notify::Watcher;
+use wgpu::naga;
+
+/// The shaders the renderer is built from, one per kind of material.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    Color,
+    Text,
+    Image,
+    Gradient,
+}
+
+impl ShaderKind {
+    pub const ALL: [ShaderKind; 4] = [ShaderKind::Color, ShaderKind::Text, ShaderKind::Image, ShaderKind::Gradient];
+
+    /// The name of the WGSL file, in `gfx/src` as well as in a shader
+    /// directory.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ShaderKind::Color => "shader.wgsl",
+            ShaderKind::Text => "text.wgsl",
+            ShaderKind::Image => "image.wgsl",
+            ShaderKind::Gradient => "gradient.wgsl",
+        }
+    }
+
+    /// The source compiled into the crate.
+    pub fn builtin(self) -> &'static str {
+        match self {
+            ShaderKind::Color => include_str!("shader.wgsl"),
+            ShaderKind::Text => include_str!("text.wgsl"),
+            ShaderKind::Image => include_str!("image.wgsl"),
+            ShaderKind::Gradient => include_str!("gradient.wgsl"),
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<ShaderKind> {
+        let name = path.file_name()?;
+        ShaderKind::ALL.into_iter().find(|kind| name == kind.file_name())
+    }
+}
+
+/// Parses and validates WGSL. The error carries naga's diagnostic, with the
+/// offending source lines.
+pub fn validate(source: &str) -> anyhow::Result<()> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|err| anyhow::anyhow!(err.emit_to_string(source)))?;
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+        .validate(&module)
+        .map_err(|err| anyhow::anyhow!(err.emit_to_string(source)))?;
+    Ok(())
+}
+
+/// Watches a shader directory for saved shaders.
+pub(crate) struct ShaderWatcher {
+    dir: PathBuf,
+    // Stops watching when dropped.
+    _watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+}
+
+impl ShaderWatcher {
+    pub(crate) fn new(dir: &Path) -> anyhow::Result<ShaderWatcher> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).context("failed to create a file watcher")?;
+        watcher
+            .watch(dir, notify::RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch shader directory {}", dir.display()))?;
+        Ok(ShaderWatcher { dir: dir.to_path_buf(), _watcher: watcher, events })
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The shaders written to since the last call, each once.
+    pub(crate) fn changed(&self) -> Vec<ShaderKind> {
+        let mut changed = Vec::new();
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    log::warn!("shader watcher: {err}");
+                    continue;
+                }
+            };
+            // Editors that save by renaming a temporary file show up as a
+            // creation.
+            if !event.kind.is_create() && !event.kind.is_modify() {
+                continue;
+            }
+            for kind in event.paths.iter().filter_map(|path| ShaderKind::from_path(path)) {
+                if !changed.contains(&kind) {
+                    changed.push(kind);
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Reads the shader `kind` from `dir`.
+pub(crate) fn read(dir: &Path, kind: ShaderKind) -> anyhow::Result<String> {
+    let path = dir.join(kind.file_name());
+    std::fs::read_to_string(&path).with_context(|| format!("failed to read shader {}", path.display()))
+}
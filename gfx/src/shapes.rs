@@ -0,0 +1,177 @@
+// Outline generators for the built-in element shapes.
+//
+// Curves are flattened into polylines whose distance from the true curve never
+// exceeds `tolerance`, in the same units as the coordinates. Outlines are in
+// logical pixels with y pointing down, and every one turns from +x towards +y,
+// which is clockwise on screen and a positive signed area. They are then
+// triangulated like any other `Element` shape.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// An axis-aligned rectangle given by its minimum corner and its size.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    pub fn min(&self) -> [f32; 2] {
+        [self.x, self.y]
+    }
+
+    pub fn max(&self) -> [f32; 2] {
+        [self.x + self.width, self.y + self.height]
+    }
+
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        let [max_x, max_y] = self.max();
+        point[0] >= self.x && point[0] <= max_x && point[1] >= self.y && point[1] <= max_y
+    }
+}
+
+// Flattening never goes below this many segments for a full turn.
+const MIN_SEGMENTS: f32 = 8.0;
+
+pub(crate) fn rect(rect: Rect) -> Vec<[f32; 2]> {
+    let [x0, y0] = rect.min();
+    let [x1, y1] = rect.max();
+    vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1]]
+}
+
+/// `radii` are given per corner, in the order (min x, min y), (max x, min y),
+/// (max x, max y), (min x, max y). Radii that do not fit are scaled down
+/// together, the way CSS does it.
+pub(crate) fn rounded_rect(rect: Rect, radii: [f32; 4], tolerance: f32) -> Vec<[f32; 2]> {
+    let mut radii = radii.map(|r| r.max(0.0));
+    let sides = [
+        (radii[0] + radii[1], rect.width),
+        (radii[1] + radii[2], rect.height),
+        (radii[2] + radii[3], rect.width),
+        (radii[3] + radii[0], rect.height),
+    ];
+    let scale = sides
+        .iter()
+        .filter(|(sum, _)| *sum > 0.0)
+        .map(|(sum, side)| side.abs() / sum)
+        .fold(1.0f32, f32::min);
+    for r in &mut radii {
+        *r *= scale;
+    }
+
+    let [x0, y0] = rect.min();
+    let [x1, y1] = rect.max();
+    let corners = [
+        ([x1 - radii[2], y1 - radii[2]], radii[2], 0.0),
+        ([x0 + radii[3], y1 - radii[3]], radii[3], FRAC_PI_2),
+        ([x0 + radii[0], y0 + radii[0]], radii[0], PI),
+        ([x1 - radii[1], y0 + radii[1]], radii[1], PI + FRAC_PI_2),
+    ];
+
+    let mut points = Vec::new();
+    for (center, radius, start) in corners {
+        arc(&mut points, center, [radius, radius], start, FRAC_PI_2, tolerance);
+    }
+    // Corners whose radii span a whole side meet in a single point.
+    points.dedup();
+    points
+}
+
+pub(crate) fn ellipse(center: [f32; 2], radii: [f32; 2], tolerance: f32) -> Vec<[f32; 2]> {
+    let mut points = Vec::new();
+    arc(&mut points, center, radii, 0.0, TAU, tolerance);
+    // The last point of a full turn is the first one again.
+    points.pop();
+    points
+}
+
+/// A pie slice from `start` to `end` (radians from +x, turning towards +y,
+/// so clockwise on screen).
+pub(crate) fn pie(center: [f32; 2], radii: [f32; 2], start: f32, end: f32, tolerance: f32) -> Vec<[f32; 2]> {
+    let sweep = (end - start).clamp(-TAU, TAU);
+    if sweep.abs() >= TAU {
+        return ellipse(center, radii, tolerance);
+    }
+
+    let mut points = vec![center];
+    arc(&mut points, center, radii, start, sweep, tolerance);
+    points
+}
+
+// Appends points along an elliptical arc, both ends included.
+fn arc(points: &mut Vec<[f32; 2]>, center: [f32; 2], radii: [f32; 2], start: f32, sweep: f32, tolerance: f32) {
+    let radius = radii[0].abs().max(radii[1].abs());
+    if radius == 0.0 {
+        points.push(center);
+        return;
+    }
+
+    let segments = segment_count(radius, sweep, tolerance);
+    for i in 0..=segments {
+        let angle = start + sweep * i as f32 / segments as f32;
+        points.push([
+            center[0] + radii[0] * angle.cos(),
+            center[1] + radii[1] * angle.sin(),
+        ]);
+    }
+}
+
+// Number of chords needed so that none of them strays further than
+// `tolerance` from a circle of `radius`.
+pub(crate) fn segment_count(radius: f32, sweep: f32, tolerance: f32) -> usize {
+    let tolerance = tolerance.max(f32::EPSILON);
+    let step = if tolerance >= radius {
+        PI
+    } else {
+        2.0 * (1.0 - tolerance / radius).acos()
+    };
+    let step = step.min(TAU / MIN_SEGMENTS);
+    (sweep.abs() / step).ceil().max(1.0) as usize
+}
+
+// Whether `point` is inside `polygon` by the non-zero winding rule, so it does
+// not matter which way the outline is wound.
+pub(crate) fn polygon_contains(polygon: &[[f32; 2]], point: [f32; 2]) -> bool {
+    let mut winding = 0;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let side = (b[0] - a[0]) * (point[1] - a[1]) - (point[0] - a[0]) * (b[1] - a[1]);
+        if a[1] <= point[1] && b[1] > point[1] && side > 0.0 {
+            winding += 1;
+        } else if a[1] > point[1] && b[1] <= point[1] && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding != 0
+}
+
+// Shortest distance from `point` to the outline through `points`.
+pub(crate) fn distance_to_outline(points: &[[f32; 2]], closed: bool, point: [f32; 2]) -> f32 {
+    let segments = match points.len() {
+        0 => return f32::INFINITY,
+        1 => 1,
+        n if closed => n,
+        n => n - 1,
+    };
+    (0..segments)
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            let d = [b[0] - a[0], b[1] - a[1]];
+            let length_sq = d[0] * d[0] + d[1] * d[1];
+            let t = if length_sq > 0.0 {
+                (((point[0] - a[0]) * d[0] + (point[1] - a[1]) * d[1]) / length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let closest = [a[0] + d[0] * t, a[1] + d[1] * t];
+            ((point[0] - closest[0]).powi(2) + (point[1] - closest[1]).powi(2)).sqrt()
+        })
+        .fold(f32::INFINITY, f32::min)
+}
@@ -1,30 +1,36 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc, time::Instant};
 
-use wgpu::util::DeviceExt;
+use anyhow::Context;
 use winit::window::Window;
 
-use crate::ui::Element;
+use crate::{
+    atlas::{AtlasTexture, GlyphAtlas},
+    batch::{DrawStats, Scissor},
+    camera::Camera,
+    gradient::GradientCache,
+    config::{self, ColorSpace, RendererConfig},
+    error::RenderError,
+    geometry::SceneBuffers,
+    pipeline::{BindGroupLayouts, PipelineTarget, Pipelines},
+    profiler::{FrameTimings, Profiler},
+    scene::{ElementId, Scene},
+    shaders::{self, ShaderKind, ShaderWatcher},
+    texture::ImageCache,
+    ui::{BuildContext, MaskOp, Material},
+};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct Vertex {
+pub struct Vertex {
     pub position: [f32; 3],
     pub _padding: [f32; 1],
     pub color: [f32; 4],
+    /// Texel coordinates in the glyph atlas; unused by colored geometry.
+    pub uv: [f32; 2],
 }
 
-const VERTICES: &[Vertex] = &[
-    Vertex { position: [-0.0868241, 0.49240386, 0.0], color: [1.0, 0.0, 0.0, 0.5], _padding: [0.0] }, // A
-    Vertex { position: [-0.49513406, 0.06958647, 0.0], color: [0.0, 1.0, 0.0, 0.5], _padding: [0.0] }, // B
-    Vertex { position: [-0.21918549, -0.44939706, 0.0], color: [0.0, 0.0, 1.0, 0.5], _padding: [0.0] }, // C
-];
-
-const INDICES: &[u16] = &[
-    0, 1, 2,
-];
-
 impl Vertex {
-    fn desc() -> wgpu::VertexBufferLayout<'static> {
+    pub(crate) fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
@@ -38,208 +44,814 @@ impl Vertex {
                     offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
                 }
             ]
         }
     }
 }
 
-pub(crate) struct State {
-    window: Arc<Window>,
+fn adapter_error(backends: wgpu::Backends, config: &RendererConfig) -> String {
+    format!(
+        "no adapter found for backends {:?} (power preference {:?}, fallback adapter {})",
+        backends, config.power_preference, config.force_fallback_adapter,
+    )
+}
+
+fn device_descriptor(adapter: &wgpu::Adapter) -> wgpu::DeviceDescriptor<'static> {
+    wgpu::DeviceDescriptor {
+        // Needed for sample counts other than 1 and 4, and for timing
+        // passes on the GPU.
+        required_features: adapter.features()
+            & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES | wgpu::Features::TIMESTAMP_QUERY),
+        ..Default::default()
+    }
+}
+
+// Features of `format` that the device may use: whatever the adapter reports
+// if adapter specific features are enabled, the guaranteed ones otherwise.
+fn format_features(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+) -> wgpu::TextureFormatFeatureFlags {
+    if device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+        adapter.get_texture_format_features(format).flags
+    } else {
+        format.guaranteed_format_features(device.features()).flags
+    }
+}
+
+// Where a frame ends up: either the swapchain of a window, or an offscreen
+// texture that can be read back on the CPU (used when running headless).
+enum RenderTarget {
+    Surface {
+        window: Arc<Window>,
+        surface: wgpu::Surface<'static>,
+        present_mode: wgpu::PresentMode,
+        max_frame_latency: u32,
+    },
+    Texture {
+        texture: wgpu::Texture,
+    },
+}
+
+// How frames are encoded and composited, fixed when the state is created.
+struct FrameFormat {
+    format: wgpu::TextureFormat,
+    // What is rendered into the frame; differs from `format` in its sRGB-ness
+    // only.
+    view_format: wgpu::TextureFormat,
+    alpha_mode: wgpu::CompositeAlphaMode,
+    sample_count: u32,
+    depth_test: bool,
+    masks: bool,
+}
+
+// The frame time graph, drawn in a pass of its own after the scene.
+struct Overlay {
+    scene: Scene,
+    graph: ElementId,
+    buffers: SceneBuffers,
+}
+
+pub struct State {
+    target: RenderTarget,
     device: wgpu::Device,
     queue: wgpu::Queue,
     size: winit::dpi::PhysicalSize<u32>,
-    surface: wgpu::Surface<'static>,
+    scale_factor: f64,
     surface_format: wgpu::TextureFormat,
-    render_pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
+    view_format: wgpu::TextureFormat,
+    alpha_mode: wgpu::CompositeAlphaMode,
+    sample_count: u32,
+    // Multisampled color target resolved into the frame, when MSAA is on.
+    msaa_view: Option<wgpu::TextureView>,
+    // Whether elements can mask their descendants, which takes a stencil
+    // buffer.
+    masks: bool,
+    // Depth, when tested, and the stencil buffer, when masks are on.
+    depth_format: Option<wgpu::TextureFormat>,
+    depth_view: Option<wgpu::TextureView>,
+    camera: Camera,
+    pipelines: Pipelines,
+    // Set when shaders are loaded from a directory.
+    shader_watcher: Option<ShaderWatcher>,
+    glyph_atlas: GlyphAtlas,
+    atlas_layout: wgpu::BindGroupLayout,
+    atlas_texture: AtlasTexture,
+    images: ImageCache,
+    gradients: GradientCache,
+    buffers: SceneBuffers,
+    draw_stats: DrawStats,
+    profiler: Profiler,
+    overlay: Option<Overlay>,
 }
 
 impl State {
-    pub(crate) async fn new(window: Arc<Window>) -> State {
-        let b = [
-                [-0.0868241, 0.49240386, 0.0], // A
-                [-0.49513406, 0.06958647, 0.0], // B
-                [-0.21918549, -0.44939706, 0.0], // C
-        ];
-        let a = Element::new()
-            .with_color([1.0, 0.0, 0.0, 0.0])
-            .with_shape(b.to_vec())
-            .build();
-
+    pub(crate) async fn new(window: Arc<Window>, config: &RendererConfig) -> anyhow::Result<State> {
+        let backends = config.backends.unwrap_or(wgpu::Backends::PRIMARY);
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
+            backends,
             ..Default::default()
         });
 
-        let surface = instance.create_surface(window.clone()).unwrap();
+        let surface = instance.create_surface(window.clone()).context("failed to create surface")?;
 
         let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: config.power_preference,
+                force_fallback_adapter: config.force_fallback_adapter,
+                compatible_surface: Some(&surface),
+            })
             .await
-            .unwrap();
+            .with_context(|| adapter_error(backends, config))?;
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
+            .request_device(&device_descriptor(&adapter))
             .await
-            .unwrap();
+            .context("failed to request device")?;
 
         let size = window.inner_size();
+        let scale_factor = window.scale_factor();
 
         let cap = surface.get_capabilities(&adapter);
-        let surface_format = cap.formats[0];
+        let (surface_format, view_format) = config::pick_format(config.color_space, &cap.formats)?;
+        let present_mode = config::check_present_mode(config.present_mode, &cap.present_modes)?;
+        anyhow::ensure!(config.max_frame_latency > 0, "the maximum frame latency must be at least 1");
+        let alpha_mode = config::pick_alpha_mode(config.composite_alpha, &cap.alpha_modes);
+        let features = format_features(&adapter, &device, view_format);
+        let sample_count = config::pick_sample_count(config.sample_count, features);
 
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+        let mut state = State::with_target(
+            RenderTarget::Surface {
+                window,
+                surface,
+                present_mode,
+                max_frame_latency: config.max_frame_latency,
+            },
+            device,
+            queue,
+            size,
+            scale_factor,
+            FrameFormat {
+                format: surface_format,
+                view_format,
+                alpha_mode,
+                sample_count,
+                depth_test: config.depth_buffer,
+                masks: config.masks,
+            },
+            config.shader_dir.as_deref(),
+        )?;
 
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor { 
-            label: Some("Render Pipeline Layout"), 
-            bind_group_layouts: &[], 
-            push_constant_ranges: &[] 
-        });
+        state.set_profiler_overlay(config.profiler_overlay);
+        // Configure surface for the first time
+        state.configure_surface();
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor { 
-            label: Some("Render Pipeline"), 
-            layout: Some(&render_pipeline_layout), 
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }, 
-            fragment: Some(wgpu::FragmentState { 
-                module: &shader, 
-                entry_point: Some("fs_main"), 
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(), 
-            }), 
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            }, 
-            depth_stencil: None, 
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            }, 
-            multiview: None, 
-            cache: None, 
-        });
+        Ok(state)
+    }
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&a),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    /// Creates a state that renders into an offscreen texture of the given size
+    /// instead of a window, on any backend. Falls back to a software adapter
+    /// when no GPU is available, so this works on CI machines.
+    pub async fn new_headless(width: u32, height: u32) -> anyhow::Result<State> {
+        Self::new_headless_with_config(width, height, &RendererConfig::default()).await
+    }
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
+    /// Like [`State::new_headless`], with the settings that apply offscreen
+    /// taken from `config`. Unless the config forces a software adapter, one
+    /// is only used when there is no other. All backends are searched unless
+    /// the config names some.
+    pub async fn new_headless_with_config(
+        width: u32,
+        height: u32,
+        config: &RendererConfig,
+    ) -> anyhow::Result<State> {
+        let backends = config.backends.unwrap_or(wgpu::Backends::all());
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
         });
 
-        let num_indices = INDICES.len() as u32;
+        let options = wgpu::RequestAdapterOptions {
+            power_preference: config.power_preference,
+            force_fallback_adapter: config.force_fallback_adapter,
+            compatible_surface: None,
+        };
+        let adapter = match instance.request_adapter(&options).await {
+            Ok(adapter) => adapter,
+            Err(_) if !config.force_fallback_adapter => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: true,
+                    ..options
+                })
+                .await
+                .with_context(|| adapter_error(backends, config))?,
+            Err(err) => return Err(err).with_context(|| adapter_error(backends, config)),
+        };
+        let (device, queue) = adapter
+            .request_device(&device_descriptor(&adapter))
+            .await
+            .context("failed to request device")?;
 
-        let state = State {
-            window,
+        let size = winit::dpi::PhysicalSize::new(width.max(1), height.max(1));
+        let surface_format = match config.color_space {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        };
+        let texture = Self::create_target_texture(&device, size, surface_format);
+        let features = format_features(&adapter, &device, surface_format);
+        let sample_count = config::pick_sample_count(config.sample_count, features);
+
+        let mut state = State::with_target(
+            RenderTarget::Texture { texture },
+            device,
+            queue,
+            size,
+            1.0,
+            FrameFormat {
+                format: surface_format,
+                view_format: surface_format,
+                // Read back frames keep their alpha, pre-multiplied.
+                alpha_mode: wgpu::CompositeAlphaMode::PreMultiplied,
+                sample_count,
+                depth_test: config.depth_buffer,
+                masks: config.masks,
+            },
+            config.shader_dir.as_deref(),
+        )?;
+        state.set_profiler_overlay(config.profiler_overlay);
+        Ok(state)
+    }
+
+    // Builds everything that does not depend on where the frame is presented.
+    fn with_target(
+        target: RenderTarget,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        size: winit::dpi::PhysicalSize<u32>,
+        scale_factor: f64,
+        frame: FrameFormat,
+        shader_dir: Option<&Path>,
+    ) -> anyhow::Result<State> {
+        let FrameFormat { format: surface_format, view_format, alpha_mode, sample_count, depth_test, masks } = frame;
+
+        let camera_bind_group_layout = Camera::bind_group_layout(&device);
+        let camera = Camera::new(&device, &camera_bind_group_layout, size, scale_factor);
+
+        let glyph_atlas = GlyphAtlas::new();
+        let atlas_layout = AtlasTexture::bind_group_layout(&device);
+        let atlas_texture = AtlasTexture::new(&device, &atlas_layout, &glyph_atlas);
+        let images = ImageCache::new(&device);
+        let gradients = GradientCache::new(&device);
+
+        let buffers = SceneBuffers::new(&device);
+        let pipeline_target = PipelineTarget { format: view_format, sample_count, depth_test, masks };
+        let depth_format = pipeline_target.depth_format();
+        let pipelines = Pipelines::new(
+            &device,
+            pipeline_target,
+            &BindGroupLayouts {
+                camera: &camera_bind_group_layout,
+                transform: buffers.transform_layout(),
+                atlas: &atlas_layout,
+                image: images.bind_group_layout(),
+                gradient: gradients.bind_group_layout(),
+            },
+        );
+        let shader_watcher = shader_dir.map(ShaderWatcher::new).transpose()?;
+
+        let msaa_view = Self::create_msaa_view(&device, size, view_format, sample_count);
+        let depth_view = Self::create_depth_view(&device, size, depth_format, sample_count);
+        let profiler = Profiler::new(&device, &queue);
+
+        let mut state = State {
+            target,
             device,
             queue,
             size,
-            surface,
+            scale_factor,
             surface_format,
-            render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            num_indices,
+            view_format,
+            alpha_mode,
+            sample_count,
+            msaa_view,
+            masks,
+            depth_format,
+            depth_view,
+            camera,
+            pipelines,
+            shader_watcher,
+            glyph_atlas,
+            atlas_layout,
+            atlas_texture,
+            images,
+            gradients,
+            buffers,
+            draw_stats: DrawStats::default(),
+            profiler,
+            overlay: None,
         };
+        if let Some(dir) = shader_dir {
+            for kind in ShaderKind::ALL {
+                if dir.join(kind.file_name()).exists() {
+                    state.load_shader(dir, kind);
+                }
+            }
+        }
+        Ok(state)
+    }
 
-        // Configure surface for the first time
-        state.configure_surface();
+    fn create_target_texture(
+        device: &wgpu::Device,
+        size: winit::dpi::PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    // The view frames are drawn into before being resolved, if MSAA is on.
+    fn create_msaa_view(
+        device: &wgpu::Device,
+        size: winit::dpi::PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        if sample_count <= 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled Target"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            // Matches the view of the frame it is resolved into.
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    fn create_depth_view(
+        device: &wgpu::Device,
+        size: winit::dpi::PhysicalSize<u32>,
+        format: Option<wgpu::TextureFormat>,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Stencil Buffer"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: format?,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    pub(crate) fn get_window(&self) -> Option<&Window> {
+        match &self.target {
+            RenderTarget::Surface { window, .. } => Some(window),
+            RenderTarget::Texture { .. } => None,
+        }
+    }
+
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.size
+    }
+
+    /// How the frame is composited with what is behind the window.
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.alpha_mode
+    }
+
+    /// Samples per pixel actually used, after checking adapter support.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
 
-        state
+    /// Draw calls and state changes of the last rendered frame, leaving out
+    /// the profiler overlay.
+    pub fn draw_stats(&self) -> DrawStats {
+        self.draw_stats
     }
 
-    pub(crate) fn get_window(&self) -> &Window {
-        &self.window
+    /// How long the last frames took on the CPU and, when
+    /// [`State::gpu_timing_supported`], on the GPU.
+    pub fn frame_timings(&self) -> FrameTimings {
+        self.profiler.timings()
+    }
+
+    /// Whether the adapter can time passes on the GPU.
+    pub fn gpu_timing_supported(&self) -> bool {
+        self.profiler.gpu_supported()
+    }
+
+    /// Waits for the GPU to finish the frames rendered so far and collects
+    /// their timings, which otherwise arrive a few frames late.
+    pub fn flush_timings(&mut self) {
+        self.profiler.collect(&self.device, true);
+    }
+
+    /// Draws a graph of the CPU time of the last frames over the top-left
+    /// corner, with their GPU time in front when it is measured.
+    pub fn set_profiler_overlay(&mut self, enabled: bool) {
+        if enabled == self.overlay.is_some() {
+            return;
+        }
+        self.overlay = enabled.then(|| {
+            let mut scene = Scene::new();
+            let graph = scene.add(self.profiler.overlay());
+            Overlay { scene, graph, buffers: SceneBuffers::sharing_layout(&self.device, &self.buffers) }
+        });
+    }
+
+    pub fn profiler_overlay(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Changes how many physical pixels make up one logical pixel of element
+    /// coordinates.
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+        self.camera.update(&self.queue, self.size, self.scale_factor);
+    }
+
+    /// Replaces the shader `kind` and rebuilds the pipelines that use it.
+    /// When `source` does not compile the current pipelines stay in use.
+    pub fn reload_shader(&mut self, kind: ShaderKind, source: &str) -> anyhow::Result<()> {
+        self.pipelines.replace(&self.device, kind, source)
+    }
+
+    fn load_shader(&mut self, dir: &Path, kind: ShaderKind) {
+        let path = dir.join(kind.file_name());
+        match shaders::read(dir, kind).and_then(|source| self.reload_shader(kind, &source)) {
+            Ok(()) => log::info!("loaded shader {}", path.display()),
+            Err(err) => log::error!("keeping the previous {kind:?} shader, {} failed: {err:#}", path.display()),
+        }
+    }
+
+    // Picks up the shaders saved since the last frame.
+    fn reload_changed_shaders(&mut self) {
+        let Some(watcher) = &self.shader_watcher else {
+            return;
+        };
+        let dir = watcher.dir().to_path_buf();
+        for kind in watcher.changed() {
+            self.load_shader(&dir, kind);
+        }
     }
 
     fn configure_surface(&self) {
+        let RenderTarget::Surface { surface, present_mode, max_frame_latency, .. } = &self.target else {
+            return;
+        };
+        if self.is_paused() {
+            return;
+        }
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: self.surface_format,
-            // Request compatibility with the sRGB-format texture view we‘re going to create later.
-            view_formats: vec![self.surface_format.add_srgb_suffix()],
-            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            // Request compatibility with the texture view we‘re going to create later.
+            view_formats: vec![self.view_format],
+            alpha_mode: self.alpha_mode,
             width: self.size.width,
             height: self.size.height,
-            desired_maximum_frame_latency: 2,
-            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: *max_frame_latency,
+            present_mode: *present_mode,
         };
-        self.surface.configure(&self.device, &surface_config);
+        surface.configure(&self.device, &surface_config);
+    }
+
+    /// Nothing is rendered while the window is minimized to a zero size.
+    pub fn is_paused(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
     }
 
-    pub(crate) fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.size = new_size;
+        if self.is_paused() {
+            // Surfaces and textures cannot be empty; keep the old ones until
+            // the window comes back.
+            return;
+        }
+        self.camera.update(&self.queue, self.size, self.scale_factor);
+        self.msaa_view = Self::create_msaa_view(&self.device, self.size, self.view_format, self.sample_count);
+        self.depth_view = Self::create_depth_view(&self.device, self.size, self.depth_format, self.sample_count);
 
-        // reconfigure the surface
-        self.configure_surface();
+        match &mut self.target {
+            // reconfigure the surface
+            RenderTarget::Surface { .. } => self.configure_surface(),
+            // offscreen textures cannot be resized, so allocate a new one
+            RenderTarget::Texture { texture } => {
+                *texture = Self::create_target_texture(&self.device, self.size, self.surface_format);
+            }
+        }
     }
 
-    pub(crate) fn render(&mut self) {
+    /// Draws the scene. Frames the surface cannot provide right now, for
+    /// example while the window is minimized, are skipped; only errors that
+    /// will not go away are returned.
+    pub fn render(&mut self, scene: &mut Scene) -> Result<(), RenderError> {
+        if self.is_paused() {
+            return Ok(());
+        }
+        let view_descriptor = wgpu::TextureViewDescriptor {
+            // Without an sRGB view the image we will be working with might
+            // not be "gamma correct", unless linear output was asked for.
+            format: Some(self.view_format),
+            ..Default::default()
+        };
+
         // Create texture view
-        let surface_texture = self
-            .surface
-            .get_current_texture()
-            .expect("failed to acquire next swapchain texture");
-        let texture_view = surface_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor {
-                // Without add_srgb_suffix() the image we will be working with
-                // might not be "gamma correct".
-                format: Some(self.surface_format.add_srgb_suffix()),
-                ..Default::default()
-            });
-
-        // Renders a GREEN screen
+        let (surface_texture, texture_view) = match &self.target {
+            RenderTarget::Surface { surface, .. } => {
+                let surface_texture = match surface.get_current_texture() {
+                    Ok(surface_texture) => surface_texture,
+                    // The window changed under the surface; the next frame
+                    // gets a fresh one.
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        self.configure_surface();
+                        return Ok(());
+                    }
+                    Err(wgpu::SurfaceError::Timeout) => {
+                        log::warn!("timed out acquiring a frame, skipping it");
+                        return Ok(());
+                    }
+                    Err(wgpu::SurfaceError::OutOfMemory) => return Err(RenderError::OutOfMemory),
+                    Err(err) => return Err(RenderError::Surface(err)),
+                };
+                let texture_view = surface_texture.texture.create_view(&view_descriptor);
+                (Some(surface_texture), texture_view)
+            }
+            RenderTarget::Texture { texture } => (None, texture.create_view(&view_descriptor)),
+        };
+
+        // Frames skipped above are not profiled: every frame begun here ends
+        // below.
+        self.profiler.begin_frame(&self.device);
+        self.reload_changed_shaders();
+
+        let viewport = self.size.to_logical::<f32>(self.scale_factor);
+        let mut images = Vec::new();
+        let mut gradients = Vec::new();
+        let mut ctx = BuildContext {
+            atlas: &mut self.glyph_atlas,
+            scale_factor: self.scale_factor as f32,
+            images: &mut images,
+            gradients: &mut gradients,
+        };
+        self.buffers.sync(&self.device, &self.queue, scene, [viewport.width, viewport.height], &mut ctx);
+        if let Some(overlay) = &mut self.overlay {
+            // Every bar moves each frame, so the graph is built and
+            // tessellated again, which is left out of the CPU time.
+            let start = Instant::now();
+            if let Some(graph) = overlay.scene.get_mut(overlay.graph) {
+                *graph = self.profiler.overlay();
+            }
+            overlay.buffers.sync(&self.device, &self.queue, &mut overlay.scene, [viewport.width, viewport.height], &mut ctx);
+            self.profiler.exclude(start.elapsed());
+        }
+        self.atlas_texture.upload(&self.device, &self.queue, &self.atlas_layout, &mut self.glyph_atlas);
+        self.images.upload(&self.device, &self.queue, &images);
+        self.gradients.upload(&self.device, &gradients);
+
         let mut encoder = self.device.create_command_encoder(&Default::default());
-        // Create the renderpass which will clear the screen.
-        let mut renderpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
+        let passes = if self.overlay.is_some() { 2 } else { 1 };
+        let mut renderpass = self.begin_pass(&mut encoder, &texture_view, 0, passes);
+        self.draw_stats = self.draw_batches(&mut renderpass, &self.buffers);
+        drop(renderpass);
+        if let Some(overlay) = &self.overlay {
+            let mut renderpass = self.begin_pass(&mut encoder, &texture_view, 1, passes);
+            self.draw_batches(&mut renderpass, &overlay.buffers);
+        }
+        self.profiler.resolve(&mut encoder, passes);
+
+        // Submit the command in the queue to execute
+        self.queue.submit([encoder.finish()]);
+        if let (Some(surface_texture), RenderTarget::Surface { window, .. }) =
+            (surface_texture, &self.target)
+        {
+            window.pre_present_notify();
+            let suboptimal = surface_texture.suboptimal;
+            surface_texture.present();
+            if suboptimal {
+                self.configure_surface();
+            }
+        }
+        self.profiler.end_frame();
+
+        Ok(())
+    }
+
+    // Starts pass `index` of `passes`, named like `profiler::PASSES`. The
+    // first one clears the frame and the last one resolves it when MSAA is
+    // on. Depth and stencil start over in every pass, so later passes are
+    // drawn on top.
+    fn begin_pass<'a>(
+        &self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        frame: &wgpu::TextureView,
+        index: usize,
+        passes: usize,
+    ) -> wgpu::RenderPass<'a> {
+        let last = index + 1 == passes;
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(if index == 0 { "Render Pass" } else { "Overlay Pass" }),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &texture_view,
+                view: self.msaa_view.as_ref().unwrap_or(frame),
                 depth_slice: None,
-                resolve_target: None,
+                resolve_target: self.msaa_view.as_ref().filter(|_| last).map(|_| frame),
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
-                    store: wgpu::StoreOp::Store,
+                    load: if index == 0 { wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT) } else { wgpu::LoadOp::Load },
+                    // Only the resolved frame is needed afterwards.
+                    store: if self.msaa_view.is_some() && last { wgpu::StoreOp::Discard } else { wgpu::StoreOp::Store },
                 },
             })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
+            depth_stencil_attachment: self.depth_view.as_ref().map(|view| wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: Some(wgpu::Operations {
+                    // Everything drawn is in front of the back plane at depth 0.
+                    load: wgpu::LoadOp::Clear(0.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                // The stencil is only there, and cleared, when masks are enabled.
+                stencil_ops: self.masks.then_some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+            }),
+            timestamp_writes: self.profiler.timestamp_writes(index),
             occlusion_query_set: None,
-        });
+        })
+    }
 
-        // If you wanted to call any drawing commands, they would go here.
-        renderpass.set_pipeline(&self.render_pipeline);
-        renderpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        renderpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        renderpass.draw_indexed(0..self.num_indices, 0, 0..1);
-        // End the renderpass.
-        drop(renderpass);
+    // Draws the batches of `buffers`, switching pipelines, bind groups,
+    // scissor rects and stencil references only where they change.
+    fn draw_batches(&self, renderpass: &mut wgpu::RenderPass<'_>, buffers: &SceneBuffers) -> DrawStats {
+        renderpass.set_bind_group(0, self.camera.bind_group(), &[]);
+        renderpass.set_vertex_buffer(0, buffers.vertex_buffer().slice(..));
+        renderpass.set_vertex_buffer(1, buffers.instance_buffer().slice(..));
+        renderpass.set_index_buffer(buffers.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+        let mut stats = DrawStats { draws: buffers.draw_count(), ..Default::default() };
+        let mut current = None;
+        let mut current_pipeline: Option<&wgpu::RenderPipeline> = None;
+        let mut current_transform = None;
+        let target = Scissor { x: 0, y: 0, width: self.size.width, height: self.size.height };
+        let mut current_scissor = target;
+        let mut current_stencil = 0;
+        for batch in buffers.batches() {
+            let state = &batch.state;
+            let scissor = state.clip.map_or(target, |clip| clip.within(target.width, target.height));
+            // Without a stencil buffer masked descendants are drawn as they
+            // are.
+            if scissor.is_empty() || (state.mask.is_some() && !self.masks) {
+                continue;
+            }
+            if current != Some((state.material, state.mask)) {
+                let (pipeline, bind_group) = match (state.mask, state.material) {
+                    (Some(MaskOp::Push), _) => (&self.pipelines.mask_push, None),
+                    (Some(MaskOp::Pop), _) => (&self.pipelines.mask_pop, None),
+                    (None, Material::Color) => (&self.pipelines.color, None),
+                    (None, Material::Text) => (&self.pipelines.text, Some(self.atlas_texture.bind_group())),
+                    (None, Material::Image { image, tiled }) => {
+                        let Some(bind_group) = self.images.bind_group(image, tiled) else {
+                            continue;
+                        };
+                        (if tiled { &self.pipelines.tiled_image } else { &self.pipelines.image }, Some(bind_group))
+                    }
+                    (None, Material::Gradient(gradient)) => {
+                        let Some(bind_group) = self.gradients.bind_group(gradient) else {
+                            continue;
+                        };
+                        (&self.pipelines.gradient, Some(bind_group))
+                    }
+                };
+                if !current_pipeline.is_some_and(|current| std::ptr::eq(current, pipeline)) {
+                    renderpass.set_pipeline(pipeline);
+                    current_pipeline = Some(pipeline);
+                    stats.pipeline_switches += 1;
+                }
+                if let Some(bind_group) = bind_group {
+                    renderpass.set_bind_group(2, bind_group, &[]);
+                    stats.bind_group_switches += 1;
+                }
+                current = Some((state.material, state.mask));
+            }
+            if current_transform != Some(state.transform_offset) {
+                renderpass.set_bind_group(1, buffers.transform_bind_group(), &[state.transform_offset]);
+                current_transform = Some(state.transform_offset);
+                stats.bind_group_switches += 1;
+            }
+            if scissor != current_scissor {
+                renderpass.set_scissor_rect(scissor.x, scissor.y, scissor.width, scissor.height);
+                current_scissor = scissor;
+            }
+            if state.stencil != current_stencil {
+                renderpass.set_stencil_reference(state.stencil);
+                current_stencil = state.stencil;
+            }
+            renderpass.draw_indexed(batch.indices.clone(), 0, state.instances.clone());
+            stats.draw_calls += 1;
+        }
+        stats
+    }
 
-        // Submit the command in the queue to execute
+    /// Copies the last rendered frame of a headless state back to the CPU as
+    /// tightly packed RGBA8 rows (`width * height * 4` bytes, top row first).
+    pub fn read_frame(&self) -> anyhow::Result<Vec<u8>> {
+        let RenderTarget::Texture { texture } = &self.target else {
+            anyhow::bail!("frames can only be read back from a headless state");
+        };
+        anyhow::ensure!(!self.is_paused(), "nothing was rendered at a size of zero");
+
+        let width = self.size.width;
+        let height = self.size.height;
+        // Rows in the staging buffer have to be aligned to 256 bytes.
+        let unpadded_bytes_per_row = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
         self.queue.submit([encoder.finish()]);
-        self.window.pre_present_notify();
-        surface_texture.present();
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait)?;
+        receiver.recv()??;
+
+        let data = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in data.chunks(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        drop(data);
+        buffer.unmap();
+
+        Ok(pixels)
     }
-}
\ No newline at end of file
+}
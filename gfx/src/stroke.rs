@@ -0,0 +1,408 @@
+// Stroke styles and the tessellation of outlines into stroke triangles.
+//
+// Each segment becomes a quad, each corner gets join geometry on its outer
+// side and each open end gets a cap. On the inner side of a corner both quads
+// end where their edges cross, so translucent strokes blend once everywhere.
+// Corners too sharp for that, whose crossing lies beyond the middle of one of
+// their segments, and outlines that cross themselves still overlap there.
+
+use crate::shapes;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Miters longer than this many half-widths fall back to a bevel.
+    pub miter_limit: f32,
+    /// Alternating dash and gap lengths. Empty draws a solid line.
+    pub dash: Vec<f32>,
+    pub dash_offset: f32,
+    /// Flattening tolerance for round joins and caps.
+    pub tolerance: f32,
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Stroke {
+        Stroke {
+            width,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            miter_limit: 4.0,
+            dash: Vec::new(),
+            dash_offset: 0.0,
+            tolerance: 0.01,
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    pub fn with_dash(mut self, dash: Vec<f32>, offset: f32) -> Self {
+        self.dash = dash;
+        self.dash_offset = offset;
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+/// Triangles covering the stroke of `points`, as a point list and indices into
+/// it. Every triangle is wound counter-clockwise.
+pub(crate) fn tessellate(points: &[[f32; 2]], closed: bool, stroke: &Stroke) -> (Vec<[f32; 2]>, Vec<u32>) {
+    let mut points = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let mut builder = Builder {
+        points: Vec::new(),
+        indices: Vec::new(),
+        half_width: stroke.width / 2.0,
+        stroke,
+    };
+    if points.len() < 2 || stroke.width <= 0.0 {
+        return (builder.points, builder.indices);
+    }
+
+    let dash_total: f32 = stroke.dash.iter().map(|len| len.max(0.0)).sum();
+    if stroke.dash.is_empty() || dash_total <= 0.0 {
+        builder.polyline(&points, closed);
+    } else {
+        if closed {
+            points.push(points[0]);
+        }
+        for dash in dashes(&points, &stroke.dash, stroke.dash_offset) {
+            builder.dash(&dash);
+        }
+    }
+
+    (builder.points, builder.indices)
+}
+
+struct Builder<'a> {
+    points: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+    half_width: f32,
+    stroke: &'a Stroke,
+}
+
+// The geometry around a point where the outline turns.
+#[derive(Copy, Clone)]
+struct Corner {
+    point: [f32; 2],
+    // Unit normals of the segments before and after, on the outer side.
+    n0: [f32; 2],
+    n1: [f32; 2],
+    // Where the inner edges of both segments cross, or `point` when they
+    // cross too far away.
+    inner: [f32; 2],
+    // Whether the inner side is on the left of the segments.
+    left_turn: bool,
+}
+
+impl Builder<'_> {
+    fn polyline(&mut self, points: &[[f32; 2]], closed: bool) {
+        // Zero-length segments have no direction to offset along.
+        let mut points = points.to_vec();
+        points.dedup();
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 2 {
+            return;
+        }
+        let points = &points[..];
+
+        let len = points.len();
+        let at = |i: usize| points[i % len];
+        let corners: Vec<Option<Corner>> = (0..len)
+            .map(|i| {
+                let is_corner = closed || (i > 0 && i < len - 1);
+                if is_corner { self.corner(at(i + len - 1), at(i), at(i + 1)) } else { None }
+            })
+            .collect();
+
+        let segments = if closed { len } else { len - 1 };
+        for i in 0..segments {
+            let (a, b) = (at(i), at(i + 1));
+            let n = scale(normal(a, b), self.half_width);
+            let mut left = [add(a, n), add(b, n)];
+            let mut right = [sub(a, n), sub(b, n)];
+            for (end, corner) in [corners[i], corners[(i + 1) % len]].into_iter().enumerate() {
+                match corner {
+                    Some(corner) if corner.left_turn => left[end] = corner.inner,
+                    Some(corner) => right[end] = corner.inner,
+                    None => {}
+                }
+            }
+            self.quad(left[0], right[0], right[1], left[1]);
+        }
+
+        for corner in corners.into_iter().flatten() {
+            self.join(corner);
+        }
+
+        if !closed {
+            let last = points.len() - 1;
+            self.cap(points[0], direction(points[1], points[0]));
+            self.cap(points[last], direction(points[last - 1], points[last]));
+        }
+    }
+
+    // A dash of zero length only has its caps, facing along the outline.
+    fn dash(&mut self, dash: &Dash) {
+        match dash.points[..] {
+            [point] => {
+                self.cap(point, scale(dash.direction, -1.0));
+                self.cap(point, dash.direction);
+            }
+            _ => self.polyline(&dash.points, false),
+        }
+    }
+
+    // `None` where the outline goes straight on.
+    fn corner(&self, prev: [f32; 2], point: [f32; 2], next: [f32; 2]) -> Option<Corner> {
+        let d0 = direction(prev, point);
+        let d1 = direction(point, next);
+        let turn = cross(d0, d1);
+        if turn.abs() < 1e-6 && dot(d0, d1) > 0.0 {
+            return None;
+        }
+
+        // The outer side of a left turn is on the right.
+        let left_turn = turn > 0.0;
+        let side = if left_turn { -1.0 } else { 1.0 };
+        let n0 = scale(normal(prev, point), side);
+        let n1 = scale(normal(point, next), side);
+
+        // The edges cross opposite the miter tip. Both segments are pulled
+        // back there, so it has to stay within the first half of each.
+        let mut inner = point;
+        if let Some(offset) = self.miter_offset(n0, n1) {
+            let pulled = sub(point, offset);
+            let pull = dot(offset, d0).abs();
+            if pull <= distance(prev, point) / 2.0 && pull <= distance(point, next) / 2.0 {
+                inner = pulled;
+            }
+        }
+        Some(Corner { point, n0, n1, inner, left_turn })
+    }
+
+    // From the corner to the miter tip, `None` for U-turns.
+    fn miter_offset(&self, n0: [f32; 2], n1: [f32; 2]) -> Option<[f32; 2]> {
+        let bisector = add(n0, n1);
+        let length = dot(bisector, bisector).sqrt();
+        let cos_half = if length > 1e-6 { dot(scale(bisector, 1.0 / length), n0) } else { 0.0 };
+        (cos_half > 1e-6).then(|| scale(bisector, self.half_width / (length * cos_half)))
+    }
+
+    // Fills the corner from where the quads end on the inner side, so it only
+    // touches them along their edges.
+    fn join(&mut self, corner: Corner) {
+        let Corner { point, n0, n1, inner, .. } = corner;
+        let a = add(point, scale(n0, self.half_width));
+        let b = add(point, scale(n1, self.half_width));
+
+        match self.stroke.join {
+            LineJoin::Bevel => self.triangle(inner, a, b),
+            LineJoin::Round => {
+                self.triangle(inner, a, point);
+                self.triangle(inner, point, b);
+                self.fan(point, n0, angle_between(n0, n1));
+            }
+            LineJoin::Miter => match self.miter_offset(n0, n1) {
+                Some(offset) if dot(offset, offset).sqrt() <= self.stroke.miter_limit * self.half_width => {
+                    let tip = add(point, offset);
+                    self.triangle(inner, a, tip);
+                    self.triangle(inner, tip, b);
+                }
+                _ => self.triangle(inner, a, b),
+            },
+        }
+    }
+
+    // `outward` points away from the line, along its direction at the end.
+    fn cap(&mut self, point: [f32; 2], outward: [f32; 2]) {
+        let n = [-outward[1], outward[0]];
+        match self.stroke.cap {
+            LineCap::Butt => {}
+            LineCap::Square => {
+                let n = scale(n, self.half_width);
+                let out = scale(outward, self.half_width);
+                self.quad(add(point, n), sub(point, n), add(sub(point, n), out), add(add(point, n), out));
+            }
+            LineCap::Round => self.fan(point, scale(n, -1.0), std::f32::consts::PI),
+        }
+    }
+
+    // Pie of the stroke's half width around `center`, starting at the unit
+    // vector `from` and sweeping `sweep` radians counter-clockwise.
+    fn fan(&mut self, center: [f32; 2], from: [f32; 2], sweep: f32) {
+        let start = from[1].atan2(from[0]);
+        let radius = self.half_width;
+        let segments = shapes::segment_count(radius, sweep, self.stroke.tolerance);
+        let at = |i: usize| {
+            let angle = start + sweep * i as f32 / segments as f32;
+            [center[0] + radius * angle.cos(), center[1] + radius * angle.sin()]
+        };
+        for i in 0..segments {
+            let (a, b) = (at(i), at(i + 1));
+            self.triangle(center, a, b);
+        }
+    }
+
+    fn quad(&mut self, a: [f32; 2], b: [f32; 2], c: [f32; 2], d: [f32; 2]) {
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+
+    fn triangle(&mut self, a: [f32; 2], b: [f32; 2], c: [f32; 2]) {
+        let area = cross(sub(b, a), sub(c, a));
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+        let (b, c) = if area > 0.0 { (b, c) } else { (c, b) };
+
+        let base = self.points.len() as u32;
+        self.points.extend([a, b, c]);
+        self.indices.extend([base, base + 1, base + 2]);
+    }
+}
+
+// One "on" piece of a dash pattern, without repeated points.
+struct Dash {
+    points: Vec<[f32; 2]>,
+    // Where the outline heads at the start of the dash.
+    direction: [f32; 2],
+}
+
+// Splits an open polyline into the "on" pieces of a dash pattern.
+fn dashes(points: &[[f32; 2]], pattern: &[f32], offset: f32) -> Vec<Dash> {
+    let mut pattern: Vec<f32> = pattern.iter().map(|len| len.max(0.0)).collect();
+    // Like SVG, an odd pattern is repeated so dashes and gaps keep alternating.
+    if pattern.len() % 2 == 1 {
+        pattern = pattern.repeat(2);
+    }
+    let total: f32 = pattern.iter().sum();
+
+    // Skip into the pattern by the offset.
+    let mut index = 0;
+    let mut remaining = pattern[0];
+    let mut skip = offset.rem_euclid(total);
+    while skip > 0.0 {
+        if skip < remaining {
+            remaining -= skip;
+            break;
+        }
+        skip -= remaining;
+        index = (index + 1) % pattern.len();
+        remaining = pattern[index];
+    }
+
+    let mut dashes = Vec::new();
+    let mut current: Option<Dash> = None;
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let length = distance(a, b);
+        let mut position = 0.0;
+        while position < length {
+            let step = remaining.min(length - position);
+            if index % 2 == 0 {
+                let dash = current.get_or_insert_with(|| Dash {
+                    points: vec![lerp(a, b, position / length)],
+                    direction: direction(a, b),
+                });
+                let end = lerp(a, b, (position + step) / length);
+                if dash.points.last() != Some(&end) {
+                    dash.points.push(end);
+                }
+            }
+            position += step;
+            remaining -= step;
+            if remaining <= 0.0 {
+                dashes.extend(current.take());
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+        }
+    }
+    dashes.extend(current);
+
+    dashes
+}
+
+fn add(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: [f32; 2], s: f32) -> [f32; 2] {
+    [a[0] * s, a[1] * s]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    let d = sub(b, a);
+    dot(d, d).sqrt()
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    add(a, scale(sub(b, a), t))
+}
+
+fn direction(from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+    scale(sub(to, from), 1.0 / distance(from, to))
+}
+
+// Unit normal on the left of the segment.
+fn normal(from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+    let d = direction(from, to);
+    [-d[1], d[0]]
+}
+
+fn angle_between(a: [f32; 2], b: [f32; 2]) -> f32 {
+    cross(a, b).atan2(dot(a, b))
+}
@@ -0,0 +1,102 @@
+// Turns polygon outlines into triangle lists.
+
+/// Triangulates a simple polygon (convex or concave, either winding) by ear
+/// clipping. Returns indices into `points`, three per triangle, every triangle
+/// with a positive signed area: turning from +x towards +y, which is clockwise
+/// on screen as y points down. The pipelines draw both windings
+/// (`cull_mode: None`), so this only keeps the output consistent.
+pub(crate) fn triangulate(points: &[[f32; 2]]) -> Vec<u32> {
+    // An explicitly closed outline repeats its first point at the end.
+    let mut n = points.len();
+    if n > 1 && points[0] == points[n - 1] {
+        n -= 1;
+    }
+    if n < 3 {
+        return Vec::new();
+    }
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    // Walk the outline with a positive signed area so every convex corner is
+    // an ear candidate and every emitted triangle has the same winding.
+    if signed_area(&points[..n]) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut indices = Vec::with_capacity((n - 2) * 3);
+    let mut i = 0;
+    let mut misses = 0;
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let prev = remaining[(i + len - 1) % len];
+        let cur = remaining[i];
+        let next = remaining[(i + 1) % len];
+
+        if is_ear(points, &remaining, prev, cur, next) {
+            indices.extend([prev as u32, cur as u32, next as u32]);
+            remaining.remove(i);
+            misses = 0;
+        } else if misses >= len {
+            // Self-intersecting or degenerate outline: no ear exists, so cut
+            // the corner anyway rather than looping forever.
+            if cross(points[prev], points[cur], points[next]) > 0.0 {
+                indices.extend([prev as u32, cur as u32, next as u32]);
+            }
+            remaining.remove(i);
+            misses = 0;
+        } else {
+            i += 1;
+            misses += 1;
+        }
+        i %= remaining.len();
+    }
+
+    let [a, b, c] = [remaining[0], remaining[1], remaining[2]];
+    if cross(points[a], points[b], points[c]) > 0.0 {
+        indices.extend([a as u32, b as u32, c as u32]);
+    }
+
+    indices
+}
+
+/// Twice the signed area of the polygon, positive when it turns from +x
+/// towards +y (clockwise on screen, counter-clockwise in a y-up frame).
+pub(crate) fn signed_area(points: &[[f32; 2]]) -> f32 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a[0] * b[1] - b[0] * a[1];
+    }
+    area
+}
+
+// Z component of (b - a) x (c - b); positive for a turn from +x towards +y.
+fn cross(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (c[1] - b[1]) - (b[1] - a[1]) * (c[0] - b[0])
+}
+
+fn is_ear(points: &[[f32; 2]], remaining: &[usize], prev: usize, cur: usize, next: usize) -> bool {
+    let (a, b, c) = (points[prev], points[cur], points[next]);
+    if cross(a, b, c) <= 0.0 {
+        return false;
+    }
+
+    // No other corner may sit inside the candidate triangle, otherwise cutting
+    // it off would overlap the rest of the polygon.
+    remaining.iter().all(|&other| {
+        let p = points[other];
+        other == prev
+            || other == cur
+            || other == next
+            || p == a
+            || p == b
+            || p == c
+            || !point_in_triangle(p, a, b, c)
+    })
+}
+
+fn point_in_triangle(p: [f32; 2], a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> bool {
+    let d1 = cross(a, b, p);
+    let d2 = cross(b, c, p);
+    let d3 = cross(c, a, p);
+    d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0
+}
@@ -0,0 +1,71 @@
+// Fonts and text runs for text elements.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::Context;
+use fontdue::layout::{CoordinateSystem, GlyphPosition, Layout, LayoutSettings, TextStyle};
+
+/// A parsed TTF/OTF font. Cheap to clone.
+#[derive(Clone)]
+pub struct Font(pub(crate) Arc<fontdue::Font>);
+
+impl Font {
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Font> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+            .map_err(anyhow::Error::msg)
+            .context("failed to parse font")?;
+        Ok(Font(Arc::new(font)))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Font> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("failed to read font {}", path.display()))?;
+        Font::from_bytes(&bytes)
+    }
+}
+
+/// A run of text drawn by an element, starting at its top-left corner.
+#[derive(Clone)]
+pub struct Text {
+    pub content: String,
+    pub font: Font,
+    /// Font size in logical pixels.
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Text {
+    pub fn new(content: impl Into<String>, font: &Font, size: f32) -> Text {
+        Text {
+            content: content.into(),
+            font: font.clone(),
+            size,
+            color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Width and height of the laid out text, in logical pixels.
+    pub fn measure(&self) -> [f32; 2] {
+        self.layout(1.0).1
+    }
+
+    // Shapes the text at `scale_factor` physical pixels per logical pixel.
+    // Glyph positions are in physical pixels, the size in logical ones.
+    pub(crate) fn layout(&self, scale_factor: f32) -> (Vec<GlyphPosition>, [f32; 2]) {
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        layout.reset(&LayoutSettings::default());
+        layout.append(
+            &[self.font.0.as_ref()],
+            &TextStyle::new(&self.content, self.size * scale_factor, 0),
+        );
+
+        let glyphs = layout.glyphs().clone();
+        let width = glyphs.iter().map(|glyph| glyph.x + glyph.width as f32).fold(0.0, f32::max);
+        (glyphs, [width / scale_factor, layout.height() / scale_factor])
+    }
+}
@@ -0,0 +1,64 @@
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// The element's transform, composed with those of its ancestors.
+struct TransformUniform {
+    matrix: mat4x4<f32>,
+};
+@group(1) @binding(0)
+var<uniform> transform: TransformUniform;
+
+@group(2) @binding(0)
+var t_atlas: texture_2d<f32>;
+@group(2) @binding(1)
+var s_atlas: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    // Texel coordinates in the glyph atlas.
+    @location(2) uv: vec2<f32>,
+};
+
+// One copy of the element, see `InstanceRaw`.
+struct InstanceInput {
+    // The first two columns of the instance's transform, and its translation.
+    @location(3) axes: vec4<f32>,
+    @location(4) offset: vec2<f32>,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color * instance.color;
+    out.uv = model.uv;
+    let position = transform.matrix * vec4<f32>(model.position, 1.0);
+    let placed = instance.axes.xy * position.x + instance.axes.zw * position.y + instance.offset;
+    out.clip_position = camera.view_proj * vec4<f32>(placed, position.zw);
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The atlas can grow between frames, so texel coordinates are only
+    // normalized here.
+    let size = vec2<f32>(textureDimensions(t_atlas));
+    let coverage = textureSample(t_atlas, s_atlas, in.uv / size).r;
+    let alpha = in.color.a * coverage;
+    return vec4<f32>(in.color.rgb * alpha, alpha);
+}
@@ -0,0 +1,248 @@
+// Images for image fills, and their GPU textures.
+//
+// An `Image` is decoded once on the CPU and shared by every element that
+// draws it. The renderer uploads an image the first time a built element uses
+// it and drops the texture again once the last `Image` handle is gone.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::Context;
+
+static NEXT_IMAGE_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageId(u64);
+
+struct ImageData {
+    id: ImageId,
+    width: u32,
+    height: u32,
+    // Straight (not pre-multiplied) sRGB RGBA8, top row first.
+    pixels: Vec<u8>,
+}
+
+/// A decoded RGBA image. Cheap to clone.
+#[derive(Clone)]
+pub struct Image(Arc<ImageData>);
+
+impl Image {
+    /// Wraps `width * height` RGBA8 pixels in sRGB, top row first.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> anyhow::Result<Image> {
+        anyhow::ensure!(width > 0 && height > 0, "image is empty");
+        anyhow::ensure!(
+            pixels.len() == width as usize * height as usize * 4,
+            "expected {} bytes of RGBA for a {width}x{height} image, got {}",
+            width as usize * height as usize * 4,
+            pixels.len(),
+        );
+        let id = ImageId(NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed));
+        Ok(Image(Arc::new(ImageData { id, width, height, pixels })))
+    }
+
+    /// Decodes a PNG or JPEG file held in memory.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Image> {
+        let decoded = image::load_from_memory(bytes).context("failed to decode image")?.into_rgba8();
+        let (width, height) = decoded.dimensions();
+        Image::from_rgba(width, height, decoded.into_raw())
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Image> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("failed to read image {}", path.display()))?;
+        Image::from_bytes(&bytes).with_context(|| format!("failed to load image {}", path.display()))
+    }
+
+    pub fn id(&self) -> ImageId {
+        self.0.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.0.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.0.height
+    }
+}
+
+/// How an image is mapped onto the bounds of the shape it fills.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ImageFit {
+    /// Fills the bounds exactly, distorting the aspect ratio if needed.
+    #[default]
+    Stretch,
+    /// As large as possible while staying inside the bounds, centered. The
+    /// rest of the shape stays empty.
+    Fit,
+    /// As small as possible while covering the bounds, centered and cropped.
+    Cover,
+    /// Repeats the image at its size in logical pixels from the top-left
+    /// corner of the bounds.
+    Tile,
+}
+
+#[derive(Clone)]
+pub(crate) struct ImageFill {
+    pub image: Image,
+    pub fit: ImageFit,
+}
+
+impl ImageFill {
+    // Texture coordinates of `point` for a fill of the box at `min` of size
+    // `size`, all in logical pixels.
+    pub(crate) fn uv(&self, min: [f32; 2], size: [f32; 2], point: [f32; 2]) -> [f32; 2] {
+        let image = [self.image.width() as f32, self.image.height() as f32];
+        let local = [point[0] - min[0], point[1] - min[1]];
+        let scale = match self.fit {
+            ImageFit::Stretch => return [0, 1].map(|axis| local[axis] / size[axis].max(f32::EPSILON)),
+            ImageFit::Tile => return [0, 1].map(|axis| local[axis] / image[axis]),
+            ImageFit::Fit => (size[0] / image[0]).min(size[1] / image[1]),
+            ImageFit::Cover => (size[0] / image[0]).max(size[1] / image[1]),
+        };
+        [0, 1].map(|axis| {
+            let drawn = image[axis] * scale;
+            (local[axis] - (size[axis] - drawn) / 2.0) / drawn.max(f32::EPSILON)
+        })
+    }
+}
+
+struct GpuImage {
+    // Dropped with the last `Image` handle.
+    image: Weak<ImageData>,
+    // The texture lives on through the bind groups.
+    clamped: wgpu::BindGroup,
+    tiled: wgpu::BindGroup,
+}
+
+/// GPU textures for the images used by the scene.
+pub(crate) struct ImageCache {
+    layout: wgpu::BindGroupLayout,
+    clamp_sampler: wgpu::Sampler,
+    repeat_sampler: wgpu::Sampler,
+    textures: HashMap<ImageId, GpuImage>,
+}
+
+impl ImageCache {
+    pub(crate) fn new(device: &wgpu::Device) -> ImageCache {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Image Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = |label, address_mode| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some(label),
+                address_mode_u: address_mode,
+                address_mode_v: address_mode,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            })
+        };
+
+        ImageCache {
+            clamp_sampler: sampler("Image Sampler", wgpu::AddressMode::ClampToEdge),
+            repeat_sampler: sampler("Tiled Image Sampler", wgpu::AddressMode::Repeat),
+            layout,
+            textures: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Uploads the images that are not on the GPU yet, and frees the ones
+    /// nothing refers to anymore.
+    pub(crate) fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, images: &[Image]) {
+        self.textures.retain(|_, gpu| gpu.image.strong_count() > 0);
+
+        for image in images {
+            if self.textures.contains_key(&image.id()) {
+                continue;
+            }
+            let size = wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth_or_array_layers: 1,
+            };
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Image"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &image.0.pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * image.width()),
+                    rows_per_image: Some(image.height()),
+                },
+                size,
+            );
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = |sampler| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Image Bind Group"),
+                    layout: &self.layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(sampler),
+                        },
+                    ],
+                })
+            };
+            let gpu = GpuImage {
+                image: Arc::downgrade(&image.0),
+                clamped: bind_group(&self.clamp_sampler),
+                tiled: bind_group(&self.repeat_sampler),
+            };
+            self.textures.insert(image.id(), gpu);
+        }
+    }
+
+    pub(crate) fn bind_group(&self, id: ImageId, tiled: bool) -> Option<&wgpu::BindGroup> {
+        let gpu = self.textures.get(&id)?;
+        Some(if tiled { &gpu.tiled } else { &gpu.clamped })
+    }
+}
@@ -0,0 +1,218 @@
+// 2D affine transforms of elements, and the GPU buffer they are read from.
+//
+// Element geometry is tessellated and uploaded untransformed. Every element
+// in a tree gets one slot in a uniform buffer holding its transform composed
+// with those of its ancestors, and each draw binds its slot at a dynamic
+// offset. Moving, rotating or scaling an element only rewrites that buffer.
+
+use std::num::NonZeroU64;
+
+use crate::geometry::changed_range;
+
+/// An affine transform of the plane, as the matrix
+/// `[a c e; b d f; 0 0 1]` given by `[a, b, c, d, e, f]`, so that `(x, y)`
+/// maps to `(a x + c y + e, b x + d y + f)`. Coordinates are logical pixels
+/// with y pointing down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform([f32; 6]);
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    pub fn from_matrix(matrix: [f32; 6]) -> Transform {
+        Transform(matrix)
+    }
+
+    pub fn matrix(&self) -> [f32; 6] {
+        self.0
+    }
+
+    pub fn translate(x: f32, y: f32) -> Transform {
+        Transform([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    /// A rotation by `angle` radians from the +x axis towards +y, which is
+    /// clockwise on screen.
+    pub fn rotate(angle: f32) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        Transform([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    pub fn scale(x: f32, y: f32) -> Transform {
+        Transform([x, 0.0, 0.0, y, 0.0, 0.0])
+    }
+
+    /// Slants the x axis by `y_angle` and the y axis by `x_angle`, both in
+    /// radians, like CSS `skew(x_angle, y_angle)`.
+    pub fn skew(x_angle: f32, y_angle: f32) -> Transform {
+        Transform([1.0, y_angle.tan(), x_angle.tan(), 1.0, 0.0, 0.0])
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(self, next: Transform) -> Transform {
+        let [a, b, c, d, e, f] = self.0;
+        let [na, nb, nc, nd, ne, nf] = next.0;
+        Transform([
+            na * a + nc * b,
+            nb * a + nd * b,
+            na * c + nc * d,
+            nb * c + nd * d,
+            na * e + nc * f + ne,
+            nb * e + nd * f + nf,
+        ])
+    }
+
+    /// The same transform with `origin` as its fixed point instead of
+    /// `(0, 0)`, for example to rotate around a center.
+    pub fn around(self, origin: [f32; 2]) -> Transform {
+        Transform::translate(-origin[0], -origin[1])
+            .then(self)
+            .then(Transform::translate(origin[0], origin[1]))
+    }
+
+    pub fn apply(&self, point: [f32; 2]) -> [f32; 2] {
+        let [a, b, c, d, e, f] = self.0;
+        [a * point[0] + c * point[1] + e, b * point[0] + d * point[1] + f]
+    }
+
+    /// `None` for transforms that collapse the plane onto a line or point.
+    pub fn inverse(&self) -> Option<Transform> {
+        let [a, b, c, d, e, f] = self.0;
+        let det = a * d - b * c;
+        if det.abs() <= f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        Some(Transform([
+            d / det,
+            -b / det,
+            -c / det,
+            a / det,
+            (c * f - d * e) / det,
+            (b * e - a * f) / det,
+        ]))
+    }
+
+    // Column-major, leaving depth alone.
+    fn to_mat4(self) -> [[f32; 4]; 4] {
+        let [a, b, c, d, e, f] = self.0;
+        [
+            [a, b, 0.0, 0.0],
+            [c, d, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [e, f, 0.0, 1.0],
+        ]
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TransformUniform {
+    matrix: [[f32; 4]; 4],
+}
+
+const UNIFORM_SIZE: u64 = std::mem::size_of::<TransformUniform>() as u64;
+
+const INITIAL_SLOTS: u64 = 64;
+
+/// Uniform slots for the transforms of every element in the scene, bound at
+/// a dynamic offset per draw.
+pub(crate) struct TransformBuffer {
+    layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // Bytes between slots, as dynamic offsets have to be aligned.
+    stride: u64,
+    // What the buffer holds, so unchanged slots are not written again.
+    written: Vec<Transform>,
+}
+
+impl TransformBuffer {
+    pub(crate) fn new(device: &wgpu::Device) -> TransformBuffer {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Transform Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: NonZeroU64::new(UNIFORM_SIZE),
+                },
+                count: None,
+            }],
+        });
+        Self::with_layout(device, layout)
+    }
+
+    /// A buffer bound through `layout`, which one created by
+    /// [`TransformBuffer::new`] uses, so both work with the same pipelines.
+    pub(crate) fn with_layout(device: &wgpu::Device, layout: wgpu::BindGroupLayout) -> TransformBuffer {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = UNIFORM_SIZE.div_ceil(alignment) * alignment;
+        let (buffer, bind_group) = Self::create(device, &layout, INITIAL_SLOTS * stride);
+        TransformBuffer { layout, buffer, bind_group, stride, written: Vec::new() }
+    }
+
+    fn create(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, size: u64) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Transform Buffer"),
+            size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Transform Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(UNIFORM_SIZE),
+                }),
+            }],
+        });
+        (buffer, bind_group)
+    }
+
+    pub(crate) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// The dynamic offset of slot `index`.
+    pub(crate) fn offset(&self, index: u32) -> u32 {
+        (index as u64 * self.stride) as u32
+    }
+
+    /// Replaces the contents of the buffer with `transforms`, one per slot,
+    /// writing only the slots that changed since the last call. Growing the
+    /// buffer drops its old contents, so then every slot is written.
+    pub(crate) fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, transforms: &[Transform]) {
+        let size = transforms.len() as u64 * self.stride;
+        if size > self.buffer.size() {
+            let size = size.next_power_of_two().max(self.buffer.size() * 2);
+            (self.buffer, self.bind_group) = Self::create(device, &self.layout, size);
+            self.written.clear();
+        }
+
+        if let Some(changed) = changed_range(&self.written, transforms) {
+            let mut bytes = vec![0; changed.len() * self.stride as usize];
+            for (slot, transform) in bytes.chunks_mut(self.stride as usize).zip(&transforms[changed.clone()]) {
+                let uniform = TransformUniform { matrix: transform.to_mat4() };
+                slot[..UNIFORM_SIZE as usize].copy_from_slice(bytemuck::bytes_of(&uniform));
+            }
+            queue.write_buffer(&self.buffer, changed.start as u64 * self.stride, &bytes);
+        }
+        self.written = transforms.to_vec();
+    }
+}
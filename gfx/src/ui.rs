@@ -1,34 +1,817 @@
-use crate::state::Vertex;
+use std::ops::Range;
 
+use crate::{
+    atlas::GlyphAtlas,
+    batch::Bounds,
+    camera,
+    gradient::{Gradient, GradientId},
+    instance::Instance,
+    layout::{self, Align, Direction, Edges, Justify, Layout, Size},
+    pointer::{Handlers, PointerEvent},
+    shapes::{self, Rect},
+    state::Vertex,
+    stroke::{self, Stroke},
+    tessellation,
+    text::Text,
+    texture::{Image, ImageFill, ImageFit, ImageId},
+    transform::Transform,
+};
+
+/// How a range of triangles is shaded, which decides the pipeline it is drawn
+/// with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Material {
+    /// Interpolated vertex colors.
+    Color,
+    /// Vertex colors masked by glyph coverage from the atlas.
+    Text,
+    /// Texels of an image, tinted by the vertex colors. Tiled images repeat
+    /// outside of their texture coordinates' unit square; others are clipped
+    /// to it.
+    Image { image: ImageId, tiled: bool },
+    /// A gradient evaluated at each fragment's position inside the element,
+    /// tinted by the vertex colors.
+    Gradient(GradientId),
+}
+
+/// How the shape of a masking element changes the stencil buffer, where
+/// masks are kept, instead of the target.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MaskOp {
+    /// Adds the shape to the masks its descendants are drawn inside.
+    Push,
+    /// Takes it away again once they are drawn.
+    Pop,
+}
+
+// What the shape of an element is painted with instead of its color.
+#[derive(Clone)]
+enum Fill {
+    Image(ImageFill),
+    Gradient(Gradient),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshDraw {
+    pub material: Material,
+    pub indices: Range<u32>,
+    /// The element of the built tree the triangles belong to, counting the
+    /// root as 0 and its descendants in the order they are drawn. Selects the
+    /// transform they are drawn with.
+    pub node: u32,
+    /// How many masks the triangles are drawn inside; they only show where
+    /// all of them cover. Mask draws count the masks they expect to find
+    /// before changing them.
+    pub masks: u32,
+    /// `Some` for the shape of a masking element, drawn into the stencil
+    /// buffer before and after its descendants.
+    pub mask: Option<MaskOp>,
+}
+
+/// Triangulated geometry produced by [`Element::build`].
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    /// Consecutive index ranges sharing a material, in draw order.
+    pub draws: Vec<MeshDraw>,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, material: Material) -> Mesh {
+        let draws = if indices.is_empty() {
+            Vec::new()
+        } else {
+            vec![MeshDraw { material, indices: 0..indices.len() as u32, node: 0, masks: 0, mask: None }]
+        };
+        Mesh { vertices, indices, draws }
+    }
+
+    /// Adds the triangles of `other` after the ones already in the mesh.
+    pub fn append(&mut self, other: Mesh) {
+        let base = self.vertices.len() as u32;
+        let index_base = self.indices.len() as u32;
+        self.indices.extend(other.indices.iter().map(|index| base + index));
+        self.vertices.extend(other.vertices);
+
+        for draw in other.draws {
+            let indices = draw.indices.start + index_base..draw.indices.end + index_base;
+            match self.draws.last_mut() {
+                Some(last)
+                    if last.material == draw.material
+                        && last.node == draw.node
+                        && last.masks == draw.masks
+                        && last.mask == draw.mask
+                        && last.indices.end == indices.start =>
+                {
+                    last.indices.end = indices.end;
+                }
+                _ => self.draws.push(MeshDraw { indices, ..draw }),
+            }
+        }
+    }
+
+    // Draws the triangles of `shape`, which index the vertices already in
+    // the mesh, once more into the stencil buffer.
+    fn add_mask(&mut self, shape: &[u32], node: u32, masks: u32, op: MaskOp) {
+        let start = self.indices.len() as u32;
+        self.indices.extend_from_slice(shape);
+        self.draws.push(MeshDraw {
+            material: Material::Color,
+            indices: start..self.indices.len() as u32,
+            node,
+            masks,
+            mask: Some(op),
+        });
+    }
+}
+
+/// What building needs besides the element itself.
+pub(crate) struct BuildContext<'a> {
+    pub atlas: &'a mut GlyphAtlas,
+    /// Physical pixels per logical pixel, which text is rasterized at.
+    pub scale_factor: f32,
+    /// Every image drawn by the built elements.
+    pub images: &'a mut Vec<Image>,
+    /// Every gradient drawn by the built elements.
+    pub gradients: &'a mut Vec<Gradient>,
+}
+
+/// A node of the UI tree.
+///
+/// The shape is given relative to the top-left corner of the rectangle the
+/// element gets from layout. Elements without a shape draw that rectangle.
 pub struct Element {
     pub shape: Vec<[f32; 3]>,
-    color: [f32; 4],
+    // `None` until a color is set, which leaves image and gradient fills
+    // untinted.
+    color: Option<[f32; 4]>,
+    // Whether the last point connects back to the first when stroked.
+    closed: bool,
+    stroke: Option<Stroke>,
+    fill: Option<Fill>,
+    z_index: i32,
+    transform: Transform,
+    // Whether descendants are clipped to the rectangle.
+    clip: bool,
+    // Whether descendants are masked by the shape.
+    mask: bool,
+    // Multiplies the alpha of the element and its descendants.
+    opacity: f32,
+    instances: Option<Vec<Instance>>,
+    pub(crate) text: Option<Text>,
+    pub(crate) layout: Layout,
+    pub(crate) children: Vec<Element>,
+    // Absolute bounds, written by the layout pass.
+    pub(crate) rect: Rect,
+    pub(crate) handlers: Handlers,
+}
+
+impl Default for Element {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Element {
     pub fn new() -> Element {
         Element { 
             shape: Vec::new(), 
-            color: [0.0, 0.0, 0.0, 0.0] 
+            color: None,
+            closed: true,
+            stroke: None,
+            fill: None,
+            z_index: 0,
+            transform: Transform::IDENTITY,
+            clip: false,
+            mask: false,
+            opacity: 1.0,
+            instances: None,
+            text: None,
+            layout: Layout::default(),
+            children: Vec::new(),
+            rect: Rect::default(),
+            handlers: Handlers::default(),
         }
     }
 
-    pub fn build(self) -> Vec<Vertex> {
-        let mut output = Vec::new();
-        for vertex in self.shape {
-            output.push(Vertex {position: vertex, _padding: [0.0], color: self.color});
+    /// Computes the rectangle of this element and all of its descendants for
+    /// a viewport of the given logical size. The scene does this before every
+    /// build; call it directly to inspect [`Element::rect`].
+    pub fn compute_layout(&mut self, viewport: [f32; 2]) {
+        layout::layout_root(self, viewport);
+    }
+
+    /// Absolute bounds from the last layout pass.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Tessellates the element and its children into triangles, children on
+    /// top. Each element contributes its filled outline, or its stroke when it
+    /// has one. A filled outline may be concave and wound either way, but must
+    /// not intersect itself. Transforms are not applied; each draw names the
+    /// element whose transform it is drawn with, see
+    /// [`Element::world_transforms`].
+    pub fn build(&self) -> Mesh {
+        let mut atlas = GlyphAtlas::new();
+        let mut images = Vec::new();
+        let mut gradients = Vec::new();
+        self.build_with(&mut BuildContext {
+            atlas: &mut atlas,
+            scale_factor: 1.0,
+            images: &mut images,
+            gradients: &mut gradients,
+        })
+    }
+
+    /// Whether `point`, in the same coordinates as [`Element::rect`], is on
+    /// the element itself, ignoring its children. Filled shapes are hit
+    /// inside their outline and stroked ones on the stroke. Elements without
+    /// a shape are hit anywhere in their rectangle, even when nothing is
+    /// drawn there, so containers receive the events of their children.
+    pub fn hit_test(&self, point: [f32; 2]) -> bool {
+        if self.shape.is_empty() {
+            return self.rect.contains(point);
+        }
+
+        let [x, y] = self.rect.min();
+        let local = [point[0] - x, point[1] - y];
+        let outline: Vec<[f32; 2]> = self.shape.iter().map(|p| [p[0], p[1]]).collect();
+        match &self.stroke {
+            Some(stroke) => shapes::distance_to_outline(&outline, self.closed, local) <= stroke.width / 2.0,
+            None => shapes::polygon_contains(&outline, local),
+        }
+    }
+
+    // Child indices leading to the innermost element under `point`, which is
+    // in window coordinates. `parent` is the world transform of the parent.
+    // Later children are drawn on top, so they are tried first. Children of
+    // clipping elements are only hit inside its rectangle, and those of
+    // masking elements only on its shape.
+    pub(crate) fn hit_path(&self, point: [f32; 2], parent: Transform) -> Option<Vec<usize>> {
+        let world = self.local_transform().then(parent);
+        let local = world.inverse().map(|inverse| inverse.apply(point));
+        let clipped = self.clip && !local.is_some_and(|local| self.rect.contains(local));
+        let masked = self.mask && !local.is_some_and(|local| self.hit_test(local));
+        if !clipped && !masked {
+            for i in self.draw_order().into_iter().rev() {
+                if let Some(mut path) = self.children[i].hit_path(point, world) {
+                    path.insert(0, i);
+                    return Some(path);
+                }
+            }
         }
+        self.hit_test(local?).then(Vec::new)
+    }
 
-        output
+    /// The transform of the element and each of its descendants composed
+    /// with those of their ancestors, indexed like [`MeshDraw::node`]. Uses
+    /// the layout of the last frame.
+    pub fn world_transforms(&self) -> Vec<Transform> {
+        let mut transforms = Vec::new();
+        self.collect_transforms(Transform::IDENTITY, &mut transforms);
+        transforms
+    }
+
+    fn collect_transforms(&self, parent: Transform, transforms: &mut Vec<Transform>) {
+        let world = self.local_transform().then(parent);
+        transforms.push(world);
+        for i in self.draw_order() {
+            self.children[i].collect_transforms(world, transforms);
+        }
+    }
+
+    // The area each element of the tree is clipped to by its ancestors, in
+    // window coordinates and indexed like `MeshDraw::node`. Under rotation
+    // or skew a clip becomes the bounds of the turned rectangle.
+    pub(crate) fn world_clips(&self) -> Vec<Option<Bounds>> {
+        let mut clips = Vec::new();
+        self.collect_clips(Transform::IDENTITY, None, &mut clips);
+        clips
+    }
+
+    fn collect_clips(&self, parent: Transform, clip: Option<Bounds>, clips: &mut Vec<Option<Bounds>>) {
+        let world = self.local_transform().then(parent);
+        clips.push(clip);
+        let clip = if self.clip {
+            let own = Bounds::new(self.rect.min(), self.rect.max()).transformed(&world);
+            Some(clip.map_or(own, |clip| clip.intersection(own)))
+        } else {
+            clip
+        };
+        for i in self.draw_order() {
+            self.children[i].collect_clips(world, clip, clips);
+        }
+    }
+
+    // The element's transform, around the top-left corner of its rectangle
+    // like its shape.
+    fn local_transform(&self) -> Transform {
+        self.transform.around(self.rect.min())
+    }
+
+    pub(crate) fn descendant_mut(&mut self, path: &[usize]) -> Option<&mut Element> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&index, rest)) => self.children.get_mut(index)?.descendant_mut(rest),
+        }
+    }
+
+    pub(crate) fn build_with(&self, ctx: &mut BuildContext) -> Mesh {
+        self.build_layer(ctx, 0, &mut 0, 0)
+    }
+
+    // Z-indices add up from the root, so `parent_z` is the effective z-index
+    // of the parent. `next_node` numbers the elements in the order of
+    // `collect_transforms`, and `masks` counts the masking ancestors.
+    fn build_layer(&self, ctx: &mut BuildContext, parent_z: i32, next_node: &mut u32, masks: u32) -> Mesh {
+        let node = *next_node;
+        *next_node += 1;
+        let z_index = parent_z.saturating_add(self.z_index);
+        let mut mesh = self.build_own(z_index);
+        let shape = self.mask.then(|| mesh.indices.clone());
+        if !mesh.indices.is_empty() {
+            match &self.fill {
+                Some(Fill::Image(fill)) => {
+                    ctx.images.push(fill.image.clone());
+                    mesh = self.apply_image(fill, mesh);
+                }
+                Some(Fill::Gradient(gradient)) => {
+                    ctx.gradients.push(gradient.clone());
+                    mesh = self.apply_gradient(gradient, mesh);
+                }
+                None => {}
+            }
+        }
+        if let Some(text) = &self.text {
+            mesh.append(self.build_text(text, ctx, z_index));
+        }
+        for draw in &mut mesh.draws {
+            draw.node = node;
+            draw.masks = masks;
+        }
+
+        // Children of a mask without a shape are masked away entirely, so
+        // they count it even when there is nothing to push.
+        let shape = shape.filter(|shape| !shape.is_empty());
+        if let Some(shape) = &shape {
+            mesh.add_mask(shape, node, masks, MaskOp::Push);
+        }
+        let child_masks = if self.mask { masks + 1 } else { masks };
+        for i in self.draw_order() {
+            mesh.append(self.children[i].build_layer(ctx, z_index, next_node, child_masks));
+        }
+        if let Some(shape) = &shape {
+            mesh.add_mask(shape, node, masks + 1, MaskOp::Pop);
+        }
+        if self.opacity != 1.0 {
+            for vertex in &mut mesh.vertices {
+                vertex.color[3] *= self.opacity;
+            }
+        }
+        mesh
+    }
+
+    // What fills are multiplied with: the element's color, or nothing while
+    // it has none.
+    fn fill_tint(&self) -> [f32; 4] {
+        self.color.unwrap_or([1.0; 4])
+    }
+
+    // Maps the image over the bounds of the mesh, which covers the whole
+    // image for `Stretch`.
+    fn apply_image(&self, fill: &ImageFill, mut mesh: Mesh) -> Mesh {
+        let mut min = [f32::INFINITY; 2];
+        let mut max = [f32::NEG_INFINITY; 2];
+        for vertex in &mesh.vertices {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(vertex.position[axis]);
+                max[axis] = max[axis].max(vertex.position[axis]);
+            }
+        }
+        let size = [max[0] - min[0], max[1] - min[1]];
+
+        for vertex in &mut mesh.vertices {
+            vertex.uv = fill.uv(min, size, [vertex.position[0], vertex.position[1]]);
+            vertex.color = self.fill_tint();
+        }
+        let material = Material::Image { image: fill.image.id(), tiled: fill.fit == ImageFit::Tile };
+        for draw in &mut mesh.draws {
+            draw.material = material;
+        }
+        mesh
+    }
+
+    // Gradients are given relative to the element, like its shape.
+    fn apply_gradient(&self, gradient: &Gradient, mut mesh: Mesh) -> Mesh {
+        let [x, y] = self.rect.min();
+        for vertex in &mut mesh.vertices {
+            vertex.uv = [vertex.position[0] - x, vertex.position[1] - y];
+            vertex.color = self.fill_tint();
+        }
+        for draw in &mut mesh.draws {
+            draw.material = Material::Gradient(gradient.id());
+        }
+        mesh
+    }
+
+    // Child indices sorted by z-index, back to front. Children with the same
+    // z-index keep their order.
+    fn draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.children.len()).collect();
+        order.sort_by_key(|&i| self.children[i].z_index);
+        order
+    }
+
+    // One quad per visible glyph, addressing the atlas in texels.
+    fn build_text(&self, text: &Text, ctx: &mut BuildContext, z_index: i32) -> Mesh {
+        let scale = ctx.scale_factor;
+        let (glyphs, _) = text.layout(scale);
+        let [x, y] = self.rect.min();
+        let z = camera::layer_depth(0.0, z_index);
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for glyph in glyphs {
+            let Some(entry) = ctx.atlas.glyph(&text.font.0, glyph.key) else {
+                continue;
+            };
+            let x0 = x + glyph.x / scale;
+            let y0 = y + glyph.y / scale;
+            let x1 = x0 + entry.width as f32 / scale;
+            let y1 = y0 + entry.height as f32 / scale;
+            let u0 = entry.x as f32;
+            let v0 = entry.y as f32;
+            let u1 = (entry.x + entry.width) as f32;
+            let v1 = (entry.y + entry.height) as f32;
+
+            let base = vertices.len() as u32;
+            for (position, uv) in [([x0, y0], [u0, v0]), ([x1, y0], [u1, v0]), ([x1, y1], [u1, v1]), ([x0, y1], [u0, v1])] {
+                vertices.push(Vertex {position: [position[0], position[1], z], _padding: [0.0], color: text.color, uv});
+            }
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+
+        Mesh::new(vertices, indices, Material::Text)
+    }
+
+    fn build_own(&self, z_index: i32) -> Mesh {
+        let points: Vec<[f32; 3]> = if self.shape.is_empty() {
+            // Nothing to see in an invisible background, unless it masks.
+            let invisible = self.color()[3] == 0.0 && self.fill.is_none() && !self.mask;
+            if invisible || self.rect.width <= 0.0 || self.rect.height <= 0.0 {
+                return Mesh::default();
+            }
+            let z = camera::layer_depth(0.0, z_index);
+            shapes::rect(self.rect).into_iter().map(|[x, y]| [x, y, z]).collect()
+        } else {
+            let [x, y] = self.rect.min();
+            self.shape.iter().map(|p| [p[0] + x, p[1] + y, camera::layer_depth(p[2], z_index)]).collect()
+        };
+
+        if let Some(stroke) = &self.stroke {
+            let outline: Vec<[f32; 2]> = points.iter().map(|p| [p[0], p[1]]).collect();
+            let (stroke_points, indices) = stroke::tessellate(&outline, self.closed, stroke);
+            let z = points.first().map_or(0.0, |p| p[2]);
+
+            let mut vertices = Vec::new();
+            for [x, y] in stroke_points {
+                vertices.push(Vertex {position: [x, y, z], _padding: [0.0], color: self.color(), uv: [0.0; 2]});
+            }
+
+            return Mesh::new(vertices, indices, Material::Color);
+        }
+
+        let outline: Vec<[f32; 2]> = points.iter().map(|p| [p[0], p[1]]).collect();
+        let indices = tessellation::triangulate(&outline);
+
+        let mut vertices = Vec::new();
+        for vertex in points {
+            vertices.push(Vertex {position: vertex, _padding: [0.0], color: self.color(), uv: [0.0; 2]});
+        }
+
+        Mesh::new(vertices, indices, Material::Color)
     }
 
     pub fn with_shape(mut self, shape: Vec<[f32; 3]>) -> Self {
         self.shape = shape;
+        self.closed = true;
+        self
+    }
+
+    /// An open line through `points`. Only visible with a stroke, since an
+    /// open line has nothing to fill.
+    pub fn with_polyline(mut self, points: Vec<[f32; 3]>) -> Self {
+        self.shape = points;
+        self.closed = false;
+        self
+    }
+
+    /// Draws the outline of the shape with the given stroke instead of
+    /// filling it.
+    pub fn with_stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = Some(stroke);
         self
     }
 
     pub fn with_color(mut self, color: [f32; 4]) -> Self {
-        self.color = color;
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_rect(self, rect: Rect) -> Self {
+        self.with_outline(shapes::rect(rect))
+    }
+
+    /// A rectangle with a separate radius for each corner, in the order
+    /// (min x, min y), (max x, min y), (max x, max y), (min x, max y).
+    /// `tolerance` is the largest distance allowed between the flattened
+    /// corners and the true arcs.
+    pub fn with_rounded_rect(self, rect: Rect, radii: [f32; 4], tolerance: f32) -> Self {
+        self.with_outline(shapes::rounded_rect(rect, radii, tolerance))
+    }
+
+    pub fn with_circle(self, center: [f32; 2], radius: f32, tolerance: f32) -> Self {
+        self.with_outline(shapes::ellipse(center, [radius, radius], tolerance))
+    }
+
+    pub fn with_ellipse(self, center: [f32; 2], radii: [f32; 2], tolerance: f32) -> Self {
+        self.with_outline(shapes::ellipse(center, radii, tolerance))
+    }
+
+    /// A pie slice of a circle between two angles, in radians measured from
+    /// the +x axis towards +y.
+    pub fn with_arc(self, center: [f32; 2], radius: f32, start: f32, end: f32, tolerance: f32) -> Self {
+        self.with_outline(shapes::pie(center, [radius, radius], start, end, tolerance))
+    }
+
+    /// Fills the shape, or the element's rectangle when it has none, with an
+    /// image. A color set on the element tints the image, and its alpha fades
+    /// it; without one the image is drawn as it is.
+    pub fn with_image(mut self, image: Image, fit: ImageFit) -> Self {
+        self.fill = Some(Fill::Image(ImageFill { image, fit }));
+        self
+    }
+
+    /// Fills the shape, or the element's rectangle when it has none, with a
+    /// gradient, tinted by the element's color like an image.
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.fill = Some(Fill::Gradient(gradient));
+        self
+    }
+
+    /// Draws a run of text at the top-left corner of the element, on top of
+    /// its shape. Auto-sized elements grow to fit the text.
+    pub fn with_text(mut self, text: Text) -> Self {
+        self.text = Some(text);
         self
     }
-}
\ No newline at end of file
+
+    /// Elements with a larger z-index are drawn in front of their siblings,
+    /// whatever order they were added in. The z-index of a child is relative
+    /// to its parent.
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Moves, rotates, scales or skews the element and its descendants,
+    /// around the top-left corner of its rectangle. Layout and the
+    /// rectangles it computes ignore transforms.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Clips the descendants of the element to its rectangle, so content
+    /// larger than a scroll view or panel does not spill out of it. The
+    /// element's own shape and text are not clipped. Clips follow transforms
+    /// exactly while they only move and scale; a rotated or skewed clip
+    /// covers the bounds of the turned rectangle.
+    pub fn with_clip(mut self, clip: bool) -> Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Masks the descendants of the element to its shape as drawn: the
+    /// filled outline, its stroke when it has one, or its rectangle when it
+    /// has no shape. Unlike [`Element::with_clip`] this follows rounded
+    /// corners, circles and any other outline, at the cost of drawing the
+    /// shape twice more. Masks nest, each one narrowing those around it.
+    /// They need a renderer configured with
+    /// [`RendererConfig::with_masks`](crate::config::RendererConfig::with_masks).
+    pub fn with_mask(mut self, mask: bool) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Fades the element and its descendants, multiplying their alpha by
+    /// `opacity`, which is clamped to [0, 1].
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.set_opacity(opacity);
+        self
+    }
+
+    /// Draws the element, with its descendants, once per instance from a
+    /// single mesh, instead of once. Only elements added to a scene directly
+    /// are instanced; on children this is ignored.
+    pub fn with_instances(mut self, instances: Vec<Instance>) -> Self {
+        self.instances = Some(instances);
+        self
+    }
+
+    pub fn with_child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn with_children(mut self, children: impl IntoIterator<Item = Element>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Called with `true` when the cursor enters the element or one of its
+    /// descendants, and with `false` when it leaves.
+    pub fn on_hover(mut self, handler: impl FnMut(bool) + 'static) -> Self {
+        self.handlers.hover = Some(Box::new(handler));
+        self
+    }
+
+    pub fn on_press(mut self, handler: impl FnMut(&PointerEvent) + 'static) -> Self {
+        self.handlers.press = Some(Box::new(handler));
+        self
+    }
+
+    pub fn on_release(mut self, handler: impl FnMut(&PointerEvent) + 'static) -> Self {
+        self.handlers.release = Some(Box::new(handler));
+        self
+    }
+
+    /// Called when a button is pressed and released again over the element.
+    pub fn on_click(mut self, handler: impl FnMut(&PointerEvent) + 'static) -> Self {
+        self.handlers.click = Some(Box::new(handler));
+        self
+    }
+
+    pub fn on_scroll(mut self, handler: impl FnMut(&PointerEvent) + 'static) -> Self {
+        self.handlers.scroll = Some(Box::new(handler));
+        self
+    }
+
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.layout.direction = direction;
+        self
+    }
+
+    pub fn with_justify(mut self, justify: Justify) -> Self {
+        self.layout.justify = justify;
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.layout.align = align;
+        self
+    }
+
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.layout.gap = gap;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: Edges) -> Self {
+        self.layout.padding = padding;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: Edges) -> Self {
+        self.layout.margin = margin;
+        self
+    }
+
+    pub fn with_size(mut self, width: Size, height: Size) -> Self {
+        self.layout.width = width;
+        self.layout.height = height;
+        self
+    }
+
+    fn with_outline(self, outline: Vec<[f32; 2]>) -> Self {
+        self.with_shape(outline.into_iter().map(|[x, y]| [x, y, 0.0]).collect())
+    }
+
+    /// Replaces the shape with a closed outline, like [`Element::with_shape`].
+    pub fn set_shape(&mut self, shape: Vec<[f32; 3]>) {
+        self.shape = shape;
+        self.closed = true;
+    }
+
+    /// Replaces the shape with an open line, like [`Element::with_polyline`].
+    pub fn set_polyline(&mut self, points: Vec<[f32; 3]>) {
+        self.shape = points;
+        self.closed = false;
+    }
+
+    pub fn set_stroke(&mut self, stroke: Option<Stroke>) {
+        self.stroke = stroke;
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = Some(color);
+    }
+
+    /// The color set on the element, transparent when none is.
+    pub fn color(&self) -> [f32; 4] {
+        self.color.unwrap_or([0.0; 4])
+    }
+
+    pub fn set_image(&mut self, image: Option<(Image, ImageFit)>) {
+        self.fill = image.map(|(image, fit)| Fill::Image(ImageFill { image, fit }));
+    }
+
+    pub fn set_gradient(&mut self, gradient: Option<Gradient>) {
+        self.fill = gradient.map(Fill::Gradient);
+    }
+
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    pub fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    /// Changing the transform of an element added to a scene through
+    /// [`Scene::set_transform`](crate::scene::Scene::set_transform) does not
+    /// tessellate it again.
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn set_clip(&mut self, clip: bool) {
+        self.clip = clip;
+    }
+
+    pub fn clips(&self) -> bool {
+        self.clip
+    }
+
+    pub fn set_mask(&mut self, mask: bool) {
+        self.mask = mask;
+    }
+
+    pub fn masks(&self) -> bool {
+        self.mask
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Like transforms, instances changed through
+    /// [`Scene::set_instances`](crate::scene::Scene::set_instances) do not
+    /// tessellate the element again.
+    pub fn set_instances(&mut self, instances: Option<Vec<Instance>>) {
+        self.instances = instances;
+    }
+
+    pub fn instances(&self) -> Option<&[Instance]> {
+        self.instances.as_deref()
+    }
+
+    pub fn set_text(&mut self, text: Option<Text>) {
+        self.text = text;
+    }
+
+    pub fn text(&self) -> Option<&Text> {
+        self.text.as_ref()
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn layout_mut(&mut self) -> &mut Layout {
+        &mut self.layout
+    }
+
+    pub fn children(&self) -> &[Element] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut Vec<Element> {
+        &mut self.children
+    }
+}
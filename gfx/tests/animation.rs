@@ -0,0 +1,250 @@
+mod common;
+
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use common::{assert_close, headless, pixel, render};
+use gfx::{
+    animation::{self, Animation, Animator, Easing, Keyframe, Repeat, Spring},
+    clock::{FrameClock, MAX_FRAME_DELTA},
+    layout::Size,
+    scene::Scene,
+    shapes::Rect,
+    transform::Transform,
+    ui::Element,
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn assert_near(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+}
+
+fn square() -> Element {
+    Element::new().with_color([1.0, 0.0, 0.0, 1.0]).with_rect(Rect::new(0.0, 0.0, 10.0, 10.0))
+}
+
+#[test]
+fn easings_start_at_zero_and_end_at_one() {
+    let easings = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ExpoInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+        Easing::ElasticOut,
+        Easing::BounceOut,
+        Easing::EASE,
+        Easing::EASE_IN,
+        Easing::EASE_OUT,
+        Easing::EASE_IN_OUT,
+    ];
+    for easing in easings {
+        assert_near(easing.apply(0.0), 0.0);
+        assert_near(easing.apply(1.0), 1.0);
+        assert_near(easing.apply(2.0), 1.0);
+    }
+
+    assert_near(Easing::CubicIn.apply(0.5), 0.125);
+    assert_near(Easing::QuadOut.apply(0.5), 0.75);
+    assert_near(Easing::EASE_IN_OUT.apply(0.5), 0.5);
+    assert_near(Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3), 0.3);
+    assert!(Easing::BackIn.apply(0.2) < 0.0);
+    assert!(Easing::BackOut.apply(0.8) > 1.0);
+}
+
+#[test]
+fn springs_settle_at_their_target() {
+    for spring in [Spring::default(), Spring::GENTLE, Spring::WOBBLY, Spring::STIFF, Spring::new(100.0, 40.0)] {
+        assert_near(spring.progress(Duration::ZERO), 0.0);
+        let settle = spring.settle_time();
+        assert!(settle > ms(100) && settle < Duration::from_secs(10), "{spring:?} settles in {settle:?}");
+        assert!((spring.progress(settle) - 1.0).abs() <= 1e-3);
+    }
+
+    // Damped less than critically, springs overshoot.
+    let overshoot = (1..100).map(|i| Spring::WOBBLY.progress(ms(i * 10))).fold(0.0, f32::max);
+    assert!(overshoot > 1.1);
+    let overshoot = (1..100).map(|i| Spring::new(100.0, 40.0).progress(ms(i * 10))).fold(0.0, f32::max);
+    assert!(overshoot <= 1.0);
+}
+
+#[test]
+fn tweens_write_their_values_and_complete() {
+    let mut scene = Scene::new();
+    let id = scene.add(square());
+    let mut animator = Animator::new();
+    let completed = Rc::new(Cell::new(0));
+    let counter = completed.clone();
+    let animation = animator.start(
+        id,
+        Animation::tween(animation::COLOR, [0.0, 0.0, 1.0, 1.0], ms(1000)).on_complete(move |_, _| {
+            counter.set(counter.get() + 1);
+        }),
+    );
+
+    animator.update(&mut scene, ms(250));
+    let color = scene.get(id).unwrap().color();
+    assert_near(color[0], 0.75);
+    assert_near(color[2], 0.25);
+    assert!(animator.is_running(animation));
+
+    animator.update(&mut scene, ms(1000));
+    assert_eq!(scene.get(id).unwrap().color(), [0.0, 0.0, 1.0, 1.0]);
+    assert!(animator.is_empty());
+    animator.update(&mut scene, ms(1000));
+    assert_eq!(completed.get(), 1);
+}
+
+#[test]
+fn eased_and_delayed_tweens() {
+    let mut scene = Scene::new();
+    let id = scene.add(square());
+    let mut animator = Animator::new();
+    animator.start(
+        id,
+        Animation::tween(animation::OPACITY, 0.0, ms(1000)).with_easing(Easing::QuadIn).with_delay(ms(500)),
+    );
+
+    animator.update(&mut scene, ms(400));
+    assert_eq!(scene.get(id).unwrap().opacity(), 1.0);
+    animator.update(&mut scene, ms(600));
+    assert_near(scene.get(id).unwrap().opacity(), 0.75);
+}
+
+#[test]
+fn keyframes_pass_through_each_value() {
+    let mut scene = Scene::new();
+    let id = scene.add(square());
+    let mut animator = Animator::new();
+    animator.start(
+        id,
+        Animation::keyframes(
+            animation::TRANSFORM,
+            [
+                Keyframe::new(ms(200), Transform::translate(10.0, 0.0)),
+                Keyframe::new(ms(400), Transform::translate(10.0, 20.0)).with_easing(Easing::CubicIn),
+            ],
+        ),
+    );
+
+    let position = |scene: &Scene| scene.get(id).unwrap().transform().apply([0.0, 0.0]);
+    animator.update(&mut scene, ms(100));
+    assert_near(position(&scene)[0], 5.0);
+    animator.update(&mut scene, ms(200));
+    assert_near(position(&scene)[0], 10.0);
+    assert_near(position(&scene)[1], 20.0 * 0.125);
+    animator.update(&mut scene, ms(200));
+    assert_near(position(&scene)[1], 20.0);
+    assert!(animator.is_empty());
+}
+
+#[test]
+fn repeats_alternate_and_loop() {
+    let mut scene = Scene::new();
+    let id = scene.add(square().with_size(Size::Px(10.0), Size::Px(10.0)));
+    let mut animator = Animator::new();
+    animator.start(
+        id,
+        Animation::tween(animation::SIZE, [20.0, 30.0], ms(100))
+            .with_repeat(Repeat::Times(2))
+            .with_alternate(true),
+    );
+
+    let size = |scene: &Scene| {
+        let layout = scene.get(id).unwrap().layout();
+        match (layout.width, layout.height) {
+            (Size::Px(width), Size::Px(height)) => [width, height],
+            other => panic!("{other:?} is not in pixels"),
+        }
+    };
+    animator.update(&mut scene, ms(150));
+    assert_near(size(&scene)[0], 15.0);
+    assert_near(size(&scene)[1], 20.0);
+    animator.update(&mut scene, ms(100));
+    assert_eq!(size(&scene), [10.0, 10.0]);
+    assert!(animator.is_empty());
+
+    let forever = animator.start(
+        id,
+        Animation::tween(animation::OPACITY, 0.0, ms(100)).starting_from(1.0).with_repeat(Repeat::Forever),
+    );
+    animator.update(&mut scene, ms(1030));
+    assert_near(scene.get(id).unwrap().opacity(), 0.7);
+    assert!(animator.is_running(forever));
+    assert!(animator.cancel(forever));
+    assert!(animator.is_empty());
+}
+
+#[test]
+fn animations_are_replaced_and_dropped_with_their_element() {
+    let mut scene = Scene::new();
+    let id = scene.add(square());
+    let mut animator = Animator::new();
+    let first = animator.start(id, Animation::tween(animation::OPACITY, 0.0, ms(100)));
+    let second = animator.start(id, Animation::tween(animation::OPACITY, 0.5, ms(100)));
+    animator.start(id, Animation::spring(animation::TRANSFORM, Transform::translate(5.0, 0.0), Spring::STIFF));
+    assert!(!animator.is_running(first));
+    assert!(animator.is_running(second));
+    assert_eq!(animator.len(), 2);
+
+    animator.update(&mut scene, ms(100));
+    assert_near(scene.get(id).unwrap().opacity(), 0.5);
+
+    // Completion callbacks may start what comes next.
+    let next = Animation::tween(animation::OPACITY, 1.0, ms(100)).on_complete(move |animator, scene| {
+        scene.remove(id);
+        animator.start(id, Animation::tween(animation::OPACITY, 0.0, ms(100)));
+    });
+    animator.start(id, next);
+    animator.update(&mut scene, ms(100));
+    assert!(!scene.contains(id));
+    assert!(!animator.is_empty());
+    animator.update(&mut scene, ms(10));
+    assert!(animator.is_empty());
+}
+
+#[test]
+fn opacity_fades_descendants() {
+    let mesh = square().with_opacity(0.5).with_child(square().with_opacity(0.5)).build();
+    let alphas: Vec<f32> = mesh.vertices.iter().map(|vertex| vertex.color[3]).collect();
+    assert!(alphas.iter().any(|&alpha| alpha == 0.5));
+    assert!(alphas.iter().any(|&alpha| alpha == 0.25));
+}
+
+#[test]
+fn animated_opacity_fades_the_frame() {
+    let mut state = headless(16, 16);
+    let mut scene = Scene::new();
+    let id = scene.add(square());
+    assert_eq!(pixel(&render(&mut state, &mut scene), 16, 4, 4), [255, 0, 0, 255]);
+
+    let mut animator = Animator::new();
+    animator.start(id, Animation::tween(animation::OPACITY, 0.5, ms(100)));
+    animator.update(&mut scene, ms(100));
+    // Pre-multiplied in an sRGB target: 0.5 encodes to 188.
+    assert_close(pixel(&render(&mut state, &mut scene), 16, 4, 4), [188, 0, 0, 128]);
+}
+
+#[test]
+fn frame_clocks_advance() {
+    let mut clock = FrameClock::new();
+    assert_eq!(clock.tick(), Duration::ZERO);
+    assert!(clock.tick() <= MAX_FRAME_DELTA);
+    clock.advance(ms(16));
+    assert_eq!(clock.delta(), ms(16));
+    assert_eq!(clock.frame(), 3);
+    assert!(clock.elapsed() >= ms(16));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{headless, pixel, square, GREEN, RED, WHITE};
+use gfx::{
+    gradient::{ColorStop, Gradient},
+    scene::Scene,
+    transform::Transform,
+    ui::Element,
+};
+
+fn gradient_square(x: f32, y: f32, gradient: &Gradient) -> Element {
+    square(x, y, 4.0, WHITE).with_gradient(gradient.clone())
+}
+
+fn blue() -> Gradient {
+    Gradient::linear([0.0, 0.0], [4.0, 0.0], [ColorStop::new(0.0, [0.0, 0.0, 1.0, 1.0])])
+}
+
+#[test]
+fn compatible_elements_share_a_draw_call() {
+    let mut state = headless(64, 64);
+    let mut scene = Scene::new();
+    for i in 0..100 {
+        let color = if i % 2 == 0 { RED } else { GREEN };
+        scene.add(square((i % 10) as f32 * 6.0, (i / 10) as f32 * 6.0, 4.0, color));
+    }
+    state.render(&mut scene).unwrap();
+
+    let stats = state.draw_stats();
+    assert_eq!(stats.draws, 100);
+    assert_eq!(stats.draw_calls, 1);
+    assert_eq!(stats.pipeline_switches, 1);
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 64, 2, 2), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 64, 8, 2), [0, 255, 0, 255]);
+}
+
+#[test]
+fn materials_are_sorted_when_nothing_overlaps() {
+    let mut state = headless(64, 64);
+    let gradient = blue();
+    let mut scene = Scene::new();
+    for i in 0..10 {
+        let x = i as f32 * 6.0;
+        if i % 2 == 0 {
+            scene.add(square(x, 0.0, 4.0, RED));
+        } else {
+            scene.add(gradient_square(x, 0.0, &gradient));
+        }
+    }
+    state.render(&mut scene).unwrap();
+
+    let stats = state.draw_stats();
+    assert_eq!(stats.draws, 10);
+    assert_eq!(stats.draw_calls, 2);
+    assert_eq!(stats.pipeline_switches, 2);
+}
+
+#[test]
+fn overlapping_draws_keep_their_order() {
+    let mut state = headless(16, 16);
+    let gradient = blue();
+    let mut scene = Scene::new();
+    scene.add(square(0.0, 0.0, 4.0, RED));
+    scene.add(gradient_square(2.0, 2.0, &gradient));
+    scene.add(square(3.0, 3.0, 4.0, GREEN));
+    state.render(&mut scene).unwrap();
+
+    assert_eq!(state.draw_stats().draw_calls, 3);
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 16, 1, 1), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 16, 2, 2), [0, 0, 255, 255]);
+    assert_eq!(pixel(&frame, 16, 4, 4), [0, 255, 0, 255]);
+}
+
+#[test]
+fn only_equal_transforms_batch() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    let a = scene.add(square(0.0, 0.0, 4.0, WHITE).with_transform(Transform::translate(8.0, 0.0)));
+    scene.add(square(0.0, 8.0, 4.0, WHITE).with_transform(Transform::translate(8.0, 0.0)));
+    state.render(&mut scene).unwrap();
+    assert_eq!(state.draw_stats().draw_calls, 1);
+
+    scene.set_transform(a, Transform::translate(16.0, 0.0));
+    state.render(&mut scene).unwrap();
+    assert_eq!(state.draw_stats().draw_calls, 2);
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 18, 2), [255; 4]);
+    assert_eq!(pixel(&frame, 32, 10, 10), [255; 4]);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{assert_close, pixel, Snapshot};
+use gfx::{
+    config::{pick_alpha_mode, CompositeAlphaMode},
+    shapes::Rect,
+    ui::Element,
+};
+
+#[test]
+fn translucent_elements_blend_premultiplied() {
+    let red = Element::new().with_color([1.0, 0.0, 0.0, 0.5]).with_rect(Rect::new(0.0, 0.0, 40.0, 64.0));
+    let blue = Element::new().with_color([0.0, 0.0, 1.0, 0.5]).with_rect(Rect::new(24.0, 0.0, 40.0, 64.0));
+    let frame = Snapshot::new("translucent").render(vec![red, blue]);
+
+    // Pre-multiplied values in an sRGB target: 0.5 encodes to 188.
+    assert_close(pixel(&frame, 64, 8, 32), [188, 0, 0, 128]);
+    // Blue over red: rgb (0.25, 0, 0.5), alpha 0.75.
+    assert_close(pixel(&frame, 64, 32, 32), [137, 0, 188, 191]);
+    assert_close(pixel(&frame, 64, 56, 32), [0, 0, 188, 128]);
+}
+
+#[test]
+fn alpha_mode_follows_the_preference_and_capabilities() {
+    use CompositeAlphaMode::*;
+
+    assert_eq!(pick_alpha_mode(Auto, &[Opaque, PreMultiplied]), PreMultiplied);
+    assert_eq!(pick_alpha_mode(Auto, &[Opaque, Inherit]), Inherit);
+    assert_eq!(pick_alpha_mode(Auto, &[Opaque]), Opaque);
+    assert_eq!(pick_alpha_mode(Opaque, &[Opaque, PreMultiplied]), Opaque);
+    assert_eq!(pick_alpha_mode(PostMultiplied, &[Opaque, PostMultiplied]), PostMultiplied);
+    // Unsupported preferences fall back like `Auto`.
+    assert_eq!(pick_alpha_mode(PostMultiplied, &[Opaque, PreMultiplied]), PreMultiplied);
+}
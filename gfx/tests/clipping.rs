@@ -0,0 +1,88 @@
+mod common;
+
+use common::{headless, pixel, render, square, RED};
+use gfx::{
+    instance::Instance,
+    layout::{Direction, Size},
+    scene::Scene,
+    transform::Transform,
+    ui::Element,
+};
+
+// A panel of `size` holding a square twice as large.
+fn panel(size: f32, clip: bool) -> Element {
+    Element::new()
+        .with_direction(Direction::Stack)
+        .with_size(Size::Px(size), Size::Px(size))
+        .with_clip(clip)
+        .with_child(square(0.0, 0.0, size * 2.0, RED))
+}
+
+#[test]
+fn descendants_stay_inside_clipping_elements() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    let id = scene.add(panel(8.0, false));
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 12, 12), [255, 0, 0, 255]);
+
+    scene.get_mut(id).unwrap().set_clip(true);
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 4, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 7, 7), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 8, 4), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 4, 8), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 12, 12), [0; 4]);
+}
+
+#[test]
+fn nested_clips_intersect() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    scene.add(
+        Element::new()
+            .with_direction(Direction::Stack)
+            .with_size(Size::Px(12.0), Size::Px(12.0))
+            .with_clip(true)
+            .with_child(panel(12.0, true).with_transform(Transform::translate(4.0, 4.0))),
+    );
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 2, 2), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 6, 6), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 11, 11), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 14, 6), [0; 4]);
+}
+
+#[test]
+fn clips_follow_transforms_and_instances() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    let id = scene.add(panel(8.0, true));
+    scene.set_transform(id, Transform::translate(8.0, 0.0));
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 4, 4), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 12, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 20, 4), [0; 4]);
+
+    scene.set_transform(id, Transform::IDENTITY);
+    scene.set_instances(id, Some(vec![Instance::at(0.0, 0.0), Instance::at(16.0, 16.0)]));
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 4, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 12, 12), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 20, 20), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 28, 28), [0; 4]);
+}
+
+#[test]
+fn clipped_content_is_not_hit() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    let id = scene.add(panel(8.0, false));
+    state.render(&mut scene).unwrap();
+    assert_eq!(scene.hit_test([12.0, 12.0]), Some(id));
+
+    scene.get_mut(id).unwrap().set_clip(true);
+    state.render(&mut scene).unwrap();
+    assert_eq!(scene.hit_test([12.0, 12.0]), None);
+    assert_eq!(scene.hit_test([4.0, 4.0]), Some(id));
+}
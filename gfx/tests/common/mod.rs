@@ -0,0 +1,200 @@
+// Golden-image snapshot harness.
+//
+// A snapshot renders a scene of elements with a headless `State`, then compares
+// the frame against `tests/golden/<name>.png`, which is committed with the
+// test. A missing golden fails the test; `GFX_UPDATE_GOLDEN=1` writes the
+// missing ones and rewrites the rest. On a mismatch the actual frame and a
+// diff image are written next to each other under the cargo target tmp dir.
+
+#![allow(dead_code)]
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use gfx::{config::RendererConfig, scene::Scene, shapes::Rect, state::State, ui::Element};
+
+pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+pub const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+pub fn square(x: f32, y: f32, size: f32, color: [f32; 4]) -> Element {
+    Element::new().with_color(color).with_rect(Rect::new(x, y, size, size))
+}
+
+pub fn headless(width: u32, height: u32) -> State {
+    pollster::block_on(State::new_headless(width, height)).expect("failed to create headless state")
+}
+
+/// Renders `scene` and reads the frame back.
+pub fn render(state: &mut State, scene: &mut Scene) -> Vec<u8> {
+    state.render(scene).unwrap();
+    state.read_frame().unwrap()
+}
+
+/// The RGBA8 pixel at `x`, `y` of a frame `width` pixels wide.
+pub fn pixel(frame: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * width + x) * 4) as usize;
+    frame[i..i + 4].try_into().unwrap()
+}
+
+/// Allows for the rounding of adapters, like [`Snapshot::with_tolerance`].
+pub fn assert_close(actual: [u8; 4], expected: [u8; 4]) {
+    let close = actual.iter().zip(&expected).all(|(a, e)| a.abs_diff(*e) <= 2);
+    assert!(close, "expected {expected:?}, got {actual:?}");
+}
+
+pub struct Snapshot {
+    name: String,
+    width: u32,
+    height: u32,
+    scale_factor: f64,
+    tolerance: u8,
+    sample_count: u32,
+}
+
+impl Snapshot {
+    pub fn new(name: &str) -> Snapshot {
+        Snapshot {
+            name: name.to_string(),
+            width: 64,
+            height: 64,
+            scale_factor: 1.0,
+            tolerance: 2,
+            sample_count: 1,
+        }
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Maximum difference allowed on any channel of a pixel before it counts
+    /// as a mismatch. Software adapters rasterize edges slightly differently,
+    /// so this is rarely zero.
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn render(&self, elements: Vec<Element>) -> Vec<u8> {
+        let config = RendererConfig::default().with_sample_count(self.sample_count);
+        let mut state = pollster::block_on(State::new_headless_with_config(self.width, self.height, &config))
+            .expect("failed to create headless state");
+        state.set_scale_factor(self.scale_factor);
+        let mut scene = Scene::new();
+        for element in elements {
+            scene.add(element);
+        }
+        state.render(&mut scene).unwrap();
+        state.read_frame().expect("failed to read back frame")
+    }
+
+    pub fn assert_matches(&self, elements: Vec<Element>) {
+        let actual = self.render(elements);
+        let golden_path = golden_dir().join(format!("{}.png", self.name));
+
+        if std::env::var_os("GFX_UPDATE_GOLDEN").is_some() {
+            write_png(&golden_path, self.width, self.height, &actual);
+            eprintln!("wrote golden image {}", golden_path.display());
+            return;
+        }
+        assert!(
+            golden_path.exists(),
+            "missing golden image {}, run with GFX_UPDATE_GOLDEN=1 to write it",
+            golden_path.display()
+        );
+
+        let (width, height, expected) = read_png(&golden_path);
+        assert_eq!(
+            (width, height),
+            (self.width, self.height),
+            "golden image {} has a different size",
+            golden_path.display()
+        );
+
+        let (mismatched, diff) = diff_images(&expected, &actual, self.tolerance);
+        if mismatched == 0 {
+            return;
+        }
+
+        let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let actual_path = out_dir.join(format!("{}.actual.png", self.name));
+        let diff_path = out_dir.join(format!("{}.diff.png", self.name));
+        write_png(&actual_path, self.width, self.height, &actual);
+        write_png(&diff_path, self.width, self.height, &diff);
+
+        panic!(
+            "{} of {} pixels differ from {} by more than {} (actual: {}, diff: {})",
+            mismatched,
+            self.width * self.height,
+            golden_path.display(),
+            self.tolerance,
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+// Returns the number of pixels outside the tolerance, and an image where those
+// pixels are solid red on top of a faded copy of the expected frame.
+fn diff_images(expected: &[u8], actual: &[u8], tolerance: u8) -> (usize, Vec<u8>) {
+    let mut mismatched = 0;
+    let mut diff = Vec::with_capacity(expected.len());
+    for (e, a) in expected.chunks_exact(4).zip(actual.chunks_exact(4)) {
+        let differs = e.iter().zip(a).any(|(e, a)| e.abs_diff(*a) > tolerance);
+        if differs {
+            mismatched += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 3 / 4) as u8;
+            diff.extend_from_slice(&[luma, luma, luma, 255]);
+        }
+    }
+    (mismatched, diff)
+}
+
+fn read_png(path: &Path) -> (u32, u32, Vec<u8>) {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::Rgba, png::BitDepth::Eight),
+        "golden images must be 8-bit RGBA"
+    );
+    pixels.truncate(info.buffer_size());
+    (info.width, info.height, pixels)
+}
+
+fn write_png(path: &Path, width: u32, height: u32, pixels: &[u8]) {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path).unwrap()), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(pixels).unwrap();
+}
@@ -0,0 +1,67 @@
+use gfx::{
+    config::{check_present_mode, pick_format, ColorSpace, PresentMode, RendererConfig},
+    scene::Scene,
+    state::State,
+    ui::Element,
+};
+use wgpu::TextureFormat;
+
+#[test]
+fn formats_follow_the_color_space() {
+    let supported = [TextureFormat::Rgba16Float, TextureFormat::Bgra8Unorm, TextureFormat::Bgra8UnormSrgb];
+    assert_eq!(
+        pick_format(ColorSpace::Srgb, &supported).unwrap(),
+        (TextureFormat::Bgra8Unorm, TextureFormat::Bgra8UnormSrgb),
+    );
+    assert_eq!(
+        pick_format(ColorSpace::Linear, &supported).unwrap(),
+        (TextureFormat::Rgba16Float, TextureFormat::Rgba16Float),
+    );
+    // An sRGB-only surface can still be written linearly through a view.
+    assert_eq!(
+        pick_format(ColorSpace::Linear, &[TextureFormat::Bgra8UnormSrgb]).unwrap(),
+        (TextureFormat::Bgra8UnormSrgb, TextureFormat::Bgra8Unorm),
+    );
+
+    let err = pick_format(ColorSpace::Srgb, &[TextureFormat::Rgba16Float]).unwrap_err();
+    assert!(err.to_string().contains("sRGB"));
+    assert!(pick_format(ColorSpace::Srgb, &[]).is_err());
+}
+
+#[test]
+fn present_modes_are_checked() {
+    let supported = [PresentMode::Fifo, PresentMode::Immediate];
+    assert_eq!(check_present_mode(PresentMode::AutoNoVsync, &[]).unwrap(), PresentMode::AutoNoVsync);
+    assert_eq!(check_present_mode(PresentMode::Immediate, &supported).unwrap(), PresentMode::Immediate);
+
+    let err = check_present_mode(PresentMode::Mailbox, &supported).unwrap_err();
+    assert!(err.to_string().contains("Mailbox"));
+}
+
+#[test]
+fn backends_default_to_the_target() {
+    assert_eq!(RendererConfig::default().backends, None);
+    let config = RendererConfig::default().with_backends(wgpu::Backends::GL);
+    assert_eq!(config.backends, Some(wgpu::Backends::GL));
+    // Offscreen, the default looks on every backend, GL included.
+    assert!(pollster::block_on(State::new_headless_with_config(16, 16, &RendererConfig::default())).is_ok());
+}
+
+#[test]
+fn linear_color_space_writes_colors_unencoded() {
+    let gray = || {
+        Element::new()
+            .with_color([0.5, 0.5, 0.5, 1.0])
+            .with_shape(vec![[0.0, 0.0, 0.0], [0.0, 16.0, 0.0], [16.0, 16.0, 0.0], [16.0, 0.0, 0.0]])
+    };
+    let render = |config: RendererConfig| {
+        let mut state = pollster::block_on(State::new_headless_with_config(16, 16, &config)).unwrap();
+        let mut scene = Scene::new();
+        scene.add(gray());
+        state.render(&mut scene).unwrap();
+        state.read_frame().unwrap()[0]
+    };
+
+    assert!(render(RendererConfig::default()).abs_diff(188) <= 1);
+    assert!(render(RendererConfig::default().with_color_space(ColorSpace::Linear)).abs_diff(128) <= 1);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::Snapshot;
+use gfx::{
+    config::RendererConfig,
+    layout::Direction,
+    scene::Scene,
+    state::State,
+    ui::Element,
+};
+
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+fn square(color: [f32; 4], z: f32) -> Element {
+    Element::new().with_color(color).with_shape(vec![
+        [8.0, 8.0, z],
+        [56.0, 8.0, z],
+        [56.0, 56.0, z],
+        [8.0, 56.0, z],
+    ])
+}
+
+fn center(frame: &[u8]) -> [u8; 4] {
+    let i = (32 * 64 + 32) * 4;
+    frame[i..i + 4].try_into().unwrap()
+}
+
+#[test]
+fn scene_draws_by_z_index_then_insertion() {
+    let mut scene = Scene::new();
+    let a = scene.add(Element::new().with_z_index(1));
+    let b = scene.add(Element::new());
+    let c = scene.add(Element::new().with_z_index(1));
+    let d = scene.add(Element::new().with_z_index(-1));
+    assert_eq!(scene.ids().collect::<Vec<_>>(), vec![d, b, a, c]);
+}
+
+#[test]
+fn z_index_beats_insertion_order() {
+    let front = square(RED, 0.0).with_z_index(2);
+    let back = square(BLUE, 0.0);
+    let frame = Snapshot::new("z_index").render(vec![front, back]);
+    assert_eq!(center(&frame), [255, 0, 0, 255]);
+
+    // The same holds between siblings inside a tree.
+    let tree = Element::new()
+        .with_direction(Direction::Stack)
+        .with_child(square(RED, 0.0).with_z_index(1))
+        .with_child(square(BLUE, 0.0));
+    let frame = Snapshot::new("z_index_children").render(vec![tree]);
+    assert_eq!(center(&frame), [255, 0, 0, 255]);
+}
+
+#[test]
+fn depth_buffer_sorts_by_vertex_z() {
+    let config = RendererConfig::default().with_depth_buffer(true);
+    let mut state = pollster::block_on(State::new_headless_with_config(64, 64, &config)).unwrap();
+
+    // Drawn last, but further back.
+    let mut scene = Scene::new();
+    scene.add(square(RED, 0.5));
+    scene.add(square(BLUE, -0.5));
+    state.render(&mut scene).unwrap();
+    assert_eq!(center(&state.read_frame().unwrap()), [255, 0, 0, 255]);
+
+    // Equal depth still draws in order, so children cover their parents.
+    let mut scene = Scene::new();
+    scene.add(square(RED, 0.0).with_child(square(BLUE, 0.0)));
+    state.resize(winit::dpi::PhysicalSize::new(64, 64));
+    state.render(&mut scene).unwrap();
+    assert_eq!(center(&state.read_frame().unwrap()), [0, 0, 255, 255]);
+}
+
+#[test]
+fn z_index_shifts_vertex_depth() {
+    let mut element = square(RED, 0.0).with_z_index(3);
+    element.compute_layout([64.0, 64.0]);
+    let low = element.build().vertices[0].position[2];
+    element.set_z_index(4);
+    let high = element.build().vertices[0].position[2];
+    assert!(high > low);
+
+    // Never pushed out of the projection.
+    element.set_z_index(i32::MAX);
+    assert!(element.build().vertices[0].position[2] <= 1.0);
+}
@@ -0,0 +1,31 @@
+use gfx::{error::RenderError, scene::Scene, state::State, ui::Element};
+use winit::dpi::PhysicalSize;
+
+#[test]
+fn zero_size_pauses_rendering() {
+    let mut state = pollster::block_on(State::new_headless(32, 32)).unwrap();
+    let mut scene = Scene::new();
+    scene.add(Element::new().with_color([1.0, 0.0, 0.0, 1.0]).with_shape(vec![
+        [0.0, 0.0, 0.0],
+        [0.0, 32.0, 0.0],
+        [32.0, 32.0, 0.0],
+    ]));
+
+    state.resize(PhysicalSize::new(0, 0));
+    assert!(state.is_paused());
+    state.render(&mut scene).unwrap();
+    assert!(state.read_frame().is_err());
+
+    state.resize(PhysicalSize::new(16, 24));
+    assert!(!state.is_paused());
+    state.render(&mut scene).unwrap();
+    assert_eq!(state.read_frame().unwrap().len(), 16 * 24 * 4);
+}
+
+#[test]
+fn errors_describe_themselves() {
+    let err = RenderError::Surface(wgpu::SurfaceError::Lost);
+    assert!(err.to_string().starts_with("failed to acquire a frame"));
+    assert!(std::error::Error::source(&err).is_some());
+    assert_eq!(RenderError::OutOfMemory.to_string(), "out of GPU memory");
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{pixel, Snapshot, WHITE};
+use gfx::{
+    gradient::{ColorStop, Gradient},
+    layout::{Edges, Size},
+    ui::{Element, Material},
+};
+
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+fn horizontal() -> Gradient {
+    Gradient::linear([0.0, 0.0], [64.0, 0.0], [ColorStop::new(1.0, WHITE), ColorStop::new(0.0, BLACK)])
+}
+
+fn full_square() -> Element {
+    Element::new().with_size(Size::Px(64.0), Size::Px(64.0))
+}
+
+#[test]
+fn stops_are_sorted() {
+    let offsets: Vec<f32> = horizontal().stops().iter().map(|stop| stop.offset).collect();
+    assert_eq!(offsets, [0.0, 1.0]);
+}
+
+#[test]
+fn vertices_carry_local_positions() {
+    let gradient = horizontal();
+    let mut element = Element::new()
+        .with_size(Size::Px(20.0), Size::Px(10.0))
+        .with_margin(Edges::all(5.0))
+        .with_gradient(gradient.clone());
+    element.compute_layout([100.0, 100.0]);
+    let mesh = element.build();
+
+    assert_eq!(mesh.draws[0].material, Material::Gradient(gradient.id()));
+    for vertex in &mesh.vertices {
+        assert_eq!(vertex.uv, [vertex.position[0] - 5.0, vertex.position[1] - 5.0]);
+    }
+}
+
+#[test]
+fn linear_gradient_varies_per_fragment() {
+    let frame = Snapshot::new("gradient_linear").render(vec![full_square().with_gradient(horizontal())]);
+
+    // Every column is brighter than the one before, even though the
+    // rectangle only has four vertices.
+    let row: Vec<u8> = (0..64).map(|x| pixel(&frame, 64, x, 32)[0]).collect();
+    assert!(row.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(row[0] < 16 && row[63] > 240);
+    assert!(row[20] > 16 && row[20] < row[44]);
+    assert_eq!(pixel(&frame, 64, 32, 0), pixel(&frame, 64, 32, 63));
+}
+
+#[test]
+fn radial_and_conic_gradients() {
+    let radial = Gradient::radial([32.0, 32.0], 32.0, [ColorStop::new(0.0, WHITE), ColorStop::new(1.0, BLACK)]);
+    let frame = Snapshot::new("gradient_radial").render(vec![full_square().with_gradient(radial)]);
+    assert!(pixel(&frame, 64, 32, 32)[0] > 240);
+    assert!(pixel(&frame, 64, 0, 0)[0] < 16);
+
+    let conic = Gradient::conic([32.0, 32.0], 0.0, [ColorStop::new(0.0, BLACK), ColorStop::new(1.0, WHITE)]);
+    let frame = Snapshot::new("gradient_conic").render(vec![full_square().with_gradient(conic)]);
+    // A quarter turn, then three quarters, from +x towards +y.
+    assert!(pixel(&frame, 64, 32, 60)[0] < pixel(&frame, 64, 4, 32)[0]);
+    assert!(pixel(&frame, 64, 4, 32)[0] < pixel(&frame, 64, 32, 4)[0]);
+}
+
+#[test]
+fn tessellation_does_not_change_the_result() {
+    let coarse = full_square().with_gradient(horizontal());
+    // The same square with extra points along its edges.
+    let fine = Element::new()
+        .with_shape(vec![
+            [0.0, 0.0, 0.0],
+            [32.0, 0.0, 0.0],
+            [64.0, 0.0, 0.0],
+            [64.0, 32.0, 0.0],
+            [64.0, 64.0, 0.0],
+            [16.0, 64.0, 0.0],
+            [0.0, 64.0, 0.0],
+            [0.0, 16.0, 0.0],
+        ])
+        .with_gradient(horizontal());
+
+    let coarse = Snapshot::new("gradient_coarse").render(vec![coarse]);
+    let fine = Snapshot::new("gradient_fine").render(vec![fine]);
+    let max_difference = coarse.iter().zip(&fine).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+    assert!(max_difference <= 1);
+}
+
+#[test]
+fn colors_tint_and_fade_gradients() {
+    let faded = full_square().with_color([1.0, 1.0, 1.0, 0.5]).with_gradient(horizontal());
+    let faded = Snapshot::new("gradient_faded").render(vec![faded]);
+    assert!(faded.chunks_exact(4).all(|pixel| pixel[3].abs_diff(128) <= 1));
+
+    let red = full_square().with_color([1.0, 0.0, 0.0, 1.0]).with_gradient(horizontal());
+    let red = Snapshot::new("gradient_tinted").render(vec![red]);
+    let [r, g, b, a] = pixel(&red, 64, 63, 32);
+    assert!(r > 250 && g == 0 && b == 0 && a == 255, "{:?}", [r, g, b, a]);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::{assert_close, pixel, Snapshot};
+use gfx::{
+    layout::Size,
+    shapes::Rect,
+    texture::{Image, ImageFit},
+    ui::{Element, Material},
+};
+
+// Red, green / blue, white.
+fn quadrants() -> Image {
+    #[rustfmt::skip]
+    let pixels = vec![
+        255, 0, 0, 255,   0, 255, 0, 255,
+        0, 0, 255, 255,   255, 255, 255, 255,
+    ];
+    Image::from_rgba(2, 2, pixels).unwrap()
+}
+
+fn uvs(element: &mut Element) -> Vec<[f32; 2]> {
+    element.compute_layout([100.0, 100.0]);
+    element.build().vertices.iter().map(|vertex| vertex.uv).collect()
+}
+
+#[test]
+fn rejects_mismatched_pixels() {
+    assert!(Image::from_rgba(2, 2, vec![0; 15]).is_err());
+    assert!(Image::from_rgba(0, 0, Vec::new()).is_err());
+    assert!(Image::from_bytes(b"not an image").is_err());
+}
+
+#[test]
+fn decodes_png() {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, 1, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+    }
+    let image = Image::from_bytes(&bytes).unwrap();
+    assert_eq!((image.width(), image.height()), (1, 2));
+}
+
+#[test]
+fn fits_map_the_bounds() {
+    let image = quadrants();
+    let rect = Rect::new(0.0, 0.0, 40.0, 20.0);
+
+    let mut stretch = Element::new().with_rect(rect).with_image(image.clone(), ImageFit::Stretch);
+    assert!(uvs(&mut stretch).contains(&[1.0, 1.0]));
+    assert_eq!(stretch.build().draws[0].material, Material::Image { image: image.id(), tiled: false });
+
+    // A square image in a 2:1 box: contained in the middle half, or cropped
+    // to the middle half.
+    let mut fit = Element::new().with_rect(rect).with_image(image.clone(), ImageFit::Fit);
+    assert!(uvs(&mut fit).contains(&[-0.5, 0.0]));
+    let mut cover = Element::new().with_rect(rect).with_image(image.clone(), ImageFit::Cover);
+    assert!(uvs(&mut cover).contains(&[0.0, 0.25]));
+
+    let mut tile = Element::new().with_rect(rect).with_image(image.clone(), ImageFit::Tile);
+    assert!(uvs(&mut tile).contains(&[20.0, 10.0]));
+    assert_eq!(tile.build().draws[0].material, Material::Image { image: image.id(), tiled: true });
+}
+
+#[test]
+fn stretched_image_renders() {
+    let element = Element::new()
+        .with_size(Size::Px(64.0), Size::Px(64.0))
+        .with_image(quadrants(), ImageFit::Stretch);
+    let frame = Snapshot::new("image_stretch").render(vec![element]);
+
+    assert_eq!(pixel(&frame, 64, 4, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 64, 60, 4), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 64, 4, 60), [0, 0, 255, 255]);
+    assert_eq!(pixel(&frame, 64, 60, 60), [255, 255, 255, 255]);
+}
+
+#[test]
+fn fitted_image_leaves_the_rest_empty() {
+    let element = Element::new()
+        .with_size(Size::Px(64.0), Size::Px(32.0))
+        .with_image(quadrants(), ImageFit::Fit);
+    let frame = Snapshot::new("image_fit").with_size(64, 32).render(vec![element]);
+
+    assert_eq!(pixel(&frame, 64, 4, 16), [0, 0, 0, 0]);
+    assert_eq!(pixel(&frame, 64, 20, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 64, 60, 16), [0, 0, 0, 0]);
+}
+
+#[test]
+fn colors_tint_and_fade_images() {
+    let element = Element::new()
+        .with_size(Size::Px(64.0), Size::Px(64.0))
+        .with_color([0.0, 1.0, 1.0, 0.5])
+        .with_image(quadrants(), ImageFit::Stretch);
+    let frame = Snapshot::new("image_tinted").render(vec![element]);
+
+    // Pre-multiplied in an sRGB target, where 0.5 encodes to 188.
+    assert_close(pixel(&frame, 64, 4, 4), [0, 0, 0, 128]);
+    assert_close(pixel(&frame, 64, 60, 4), [0, 188, 0, 128]);
+    assert_close(pixel(&frame, 64, 60, 60), [0, 188, 188, 128]);
+}
+
+#[test]
+fn transparent_colors_hide_images() {
+    let element = Element::new()
+        .with_size(Size::Px(64.0), Size::Px(64.0))
+        .with_color([0.0; 4])
+        .with_image(quadrants(), ImageFit::Stretch);
+    let frame = Snapshot::new("image_transparent").render(vec![element]);
+
+    assert_eq!(pixel(&frame, 64, 60, 60), [0, 0, 0, 0]);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::{headless, pixel, square, WHITE};
+use gfx::{
+    instance::Instance,
+    scene::Scene,
+    transform::Transform,
+};
+
+#[test]
+fn instances_draw_copies_of_one_mesh() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    let id = scene.add(square(0.0, 0.0, 4.0, WHITE).with_instances(vec![
+        Instance::new(Transform::IDENTITY, [1.0, 0.0, 0.0, 1.0]),
+        Instance::new(Transform::translate(10.0, 0.0), [0.0, 1.0, 0.0, 1.0]),
+        // Scaled around the marker's corner, then moved.
+        Instance::new(Transform::scale(2.0, 2.0).then(Transform::translate(20.0, 20.0)), [0.0, 0.0, 1.0, 1.0]),
+    ]));
+    // Drawn once, next to the instanced one.
+    scene.add(square(0.0, 0.0, 4.0, WHITE).with_color([1.0, 1.0, 0.0, 1.0]).with_transform(Transform::translate(0.0, 10.0)));
+
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 2, 2), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 12, 2), [0, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 27, 27), [0, 0, 255, 255]);
+    assert_eq!(pixel(&frame, 32, 2, 12), [255, 255, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 6, 2), [0, 0, 0, 0]);
+
+    scene.set_instances(id, Some(vec![Instance::at(10.0, 20.0)]));
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 2, 2), [0, 0, 0, 0]);
+    assert_eq!(pixel(&frame, 32, 12, 22), [255, 255, 255, 255]);
+
+    // No instances, nothing drawn.
+    scene.set_instances(id, Some(Vec::new()));
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 12, 22), [0, 0, 0, 0]);
+
+    scene.set_instances(id, None);
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 2, 2), [255, 255, 255, 255]);
+}
+
+#[test]
+fn every_instance_is_hit() {
+    let mut element = square(0.0, 0.0, 4.0, WHITE).with_instances((0..100).map(|i| Instance::at(i as f32 * 10.0, 0.0)).collect());
+    element.compute_layout([1000.0, 100.0]);
+    let mut scene = Scene::new();
+    let id = scene.add(element);
+
+    assert_eq!(scene.hit_test([2.0, 2.0]), Some(id));
+    assert_eq!(scene.hit_test([992.0, 2.0]), Some(id));
+    assert_eq!(scene.hit_test([6.0, 2.0]), None);
+}
@@ -0,0 +1,106 @@
+use gfx::{
+    layout::{Align, Direction, Edges, Justify, Size},
+    shapes::Rect,
+    ui::Element,
+};
+
+fn boxed(width: f32, height: f32) -> Element {
+    Element::new().with_size(Size::Px(width), Size::Px(height))
+}
+
+#[test]
+fn row_with_padding_gap_and_fill() {
+    let mut root = Element::new()
+        .with_direction(Direction::Row)
+        .with_size(Size::Fill(1.0), Size::Px(50.0))
+        .with_padding(Edges::all(5.0))
+        .with_gap(10.0)
+        .with_children([
+            boxed(20.0, 20.0),
+            Element::new().with_size(Size::Fill(1.0), Size::Px(20.0)),
+            Element::new().with_size(Size::Fill(3.0), Size::Px(20.0)),
+        ]);
+    root.compute_layout([200.0, 100.0]);
+
+    assert_eq!(root.rect(), Rect::new(0.0, 0.0, 200.0, 50.0));
+    let rects: Vec<Rect> = root.children().iter().map(Element::rect).collect();
+    // 190 of content, minus 20 for the fixed box and 20 of gaps, leaves 150.
+    assert_eq!(rects[0], Rect::new(5.0, 5.0, 20.0, 20.0));
+    assert_eq!(rects[1], Rect::new(35.0, 5.0, 37.5, 20.0));
+    assert_eq!(rects[2], Rect::new(82.5, 5.0, 112.5, 20.0));
+}
+
+#[test]
+fn column_justify_and_align() {
+    let column = |justify, align| {
+        let mut root = Element::new()
+            .with_size(Size::Px(100.0), Size::Px(100.0))
+            .with_justify(justify)
+            .with_align(align)
+            .with_children([boxed(20.0, 20.0), boxed(40.0, 20.0)]);
+        root.compute_layout([500.0, 500.0]);
+        root.children().iter().map(Element::rect).collect::<Vec<_>>()
+    };
+
+    let centered = column(Justify::Center, Align::Center);
+    assert_eq!(centered[0], Rect::new(40.0, 30.0, 20.0, 20.0));
+    assert_eq!(centered[1], Rect::new(30.0, 50.0, 40.0, 20.0));
+
+    let spread = column(Justify::SpaceBetween, Align::End);
+    assert_eq!(spread[0], Rect::new(80.0, 0.0, 20.0, 20.0));
+    assert_eq!(spread[1], Rect::new(60.0, 80.0, 40.0, 20.0));
+
+    let stretched = column(Justify::End, Align::Stretch);
+    // Fixed sizes are not stretched; only `Size::Auto` is.
+    assert_eq!(stretched[0], Rect::new(0.0, 60.0, 20.0, 20.0));
+}
+
+#[test]
+fn auto_size_wraps_children_and_margins() {
+    let mut root = Element::new()
+        .with_direction(Direction::Row)
+        .with_padding(Edges::symmetric(4.0, 2.0))
+        .with_margin(Edges::all(10.0))
+        .with_children([
+            boxed(20.0, 30.0).with_margin(Edges { right: 6.0, ..Edges::default() }),
+            Element::new()
+                .with_align(Align::Stretch)
+                .with_child(boxed(10.0, 10.0)),
+        ]);
+    root.compute_layout([500.0, 500.0]);
+
+    assert_eq!(root.rect(), Rect::new(10.0, 10.0, 44.0, 34.0));
+    assert_eq!(root.children()[1].rect(), Rect::new(40.0, 12.0, 10.0, 10.0));
+    assert_eq!(root.children()[1].children()[0].rect(), Rect::new(40.0, 12.0, 10.0, 10.0));
+}
+
+#[test]
+fn stack_overlaps_children() {
+    let mut root = Element::new()
+        .with_direction(Direction::Stack)
+        .with_align(Align::Center)
+        .with_size(Size::Fill(1.0), Size::Fill(1.0))
+        .with_children([
+            Element::new().with_size(Size::Fill(1.0), Size::Fill(1.0)),
+            boxed(10.0, 10.0),
+        ]);
+    root.compute_layout([100.0, 60.0]);
+
+    assert_eq!(root.children()[0].rect(), Rect::new(0.0, 0.0, 100.0, 60.0));
+    assert_eq!(root.children()[1].rect(), Rect::new(45.0, 25.0, 10.0, 10.0));
+}
+
+#[test]
+fn shapes_follow_their_layout_rect() {
+    let mut root = Element::new()
+        .with_direction(Direction::Row)
+        .with_children([
+            boxed(30.0, 10.0),
+            Element::new().with_color([1.0; 4]).with_rect(Rect::new(0.0, 0.0, 5.0, 5.0)),
+        ]);
+    root.compute_layout([100.0, 100.0]);
+
+    let mesh = root.children()[1].build();
+    let min_x = mesh.vertices.iter().map(|v| v.position[0]).fold(f32::MAX, f32::min);
+    assert_eq!(min_x, 30.0);
+}
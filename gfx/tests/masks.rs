@@ -0,0 +1,126 @@
+mod common;
+
+use common::{headless, pixel, render, square, GREEN, RED};
+use gfx::{
+    config::RendererConfig,
+    layout::{Direction, Size},
+    scene::Scene,
+    shapes::Rect,
+    state::State,
+    ui::{Element, MaskOp},
+};
+
+fn with_masks(width: u32, height: u32) -> State {
+    let config = RendererConfig::default().with_masks(true);
+    pollster::block_on(State::new_headless_with_config(width, height, &config)).unwrap()
+}
+
+// An invisible container of `size` masking whatever it holds.
+fn container(size: f32) -> Element {
+    Element::new()
+        .with_direction(Direction::Stack)
+        .with_size(Size::Px(size), Size::Px(size))
+        .with_color([0.0; 4])
+        .with_mask(true)
+}
+
+#[test]
+fn masked_descendants_are_drawn_between_push_and_pop() {
+    let mut element = container(16.0)
+        .with_circle([8.0, 8.0], 8.0, 0.1)
+        .with_child(container(8.0).with_child(square(0.0, 0.0, 16.0, RED)));
+    element.compute_layout([32.0, 32.0]);
+    let mesh = element.build();
+
+    let steps: Vec<(u32, u32, Option<MaskOp>)> =
+        mesh.draws.iter().map(|draw| (draw.node, draw.masks, draw.mask)).collect();
+    assert_eq!(
+        steps,
+        [
+            (0, 0, None),
+            (0, 0, Some(MaskOp::Push)),
+            (1, 1, None),
+            (1, 1, Some(MaskOp::Push)),
+            (2, 2, None),
+            (1, 2, Some(MaskOp::Pop)),
+            (0, 1, Some(MaskOp::Pop)),
+        ]
+    );
+    // Mask draws repeat the triangles of the shape.
+    let push = &mesh.indices[mesh.draws[1].indices.start as usize..mesh.draws[1].indices.end as usize];
+    let pop = &mesh.indices[mesh.draws[6].indices.start as usize..mesh.draws[6].indices.end as usize];
+    assert_eq!(push, pop);
+    assert_eq!(push, &mesh.indices[..push.len()]);
+}
+
+#[test]
+fn descendants_only_show_inside_the_shape() {
+    let mut state = with_masks(32, 32);
+    let mut scene = Scene::new();
+    let id = scene.add(
+        container(16.0)
+            .with_circle([8.0, 8.0], 8.0, 0.1)
+            .with_child(square(0.0, 0.0, 32.0, RED)),
+    );
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 8, 8), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 1, 1), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 14, 14), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 20, 8), [0; 4]);
+
+    assert_eq!(scene.hit_test([8.0, 8.0]), Some(id));
+    assert_eq!(scene.hit_test([20.0, 8.0]), None);
+
+    scene.get_mut(id).unwrap().set_mask(false);
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 1, 1), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 20, 8), [255, 0, 0, 255]);
+}
+
+#[test]
+fn nested_masks_intersect_and_are_undone() {
+    let mut state = with_masks(32, 32);
+    let mut scene = Scene::new();
+    scene.add(
+        container(16.0)
+            .with_circle([8.0, 8.0], 8.0, 0.1)
+            .with_child(
+                container(16.0)
+                    .with_rect(Rect::new(8.0, 8.0, 8.0, 8.0))
+                    .with_child(square(0.0, 0.0, 16.0, RED)),
+            ),
+    );
+    // Drawn after both masks are popped again, so nothing masks it.
+    scene.add(square(10.0, 10.0, 4.0, GREEN));
+
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 13, 9), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 4, 4), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 15, 15), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 12, 12), [0, 255, 0, 255]);
+}
+
+#[test]
+fn masks_without_a_shape_use_the_rectangle() {
+    let mut state = with_masks(32, 32);
+    let mut scene = Scene::new();
+    scene.add(container(8.0).with_child(square(0.0, 0.0, 16.0, RED)));
+    scene.add(square(0.0, 16.0, 16.0, GREEN));
+
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 4, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(&frame, 32, 12, 12), [0; 4]);
+    assert_eq!(pixel(&frame, 32, 4, 20), [0, 255, 0, 255]);
+}
+
+#[test]
+fn masks_need_a_stencil_buffer() {
+    assert!(!RendererConfig::default().masks);
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    scene.add(container(8.0).with_child(square(0.0, 0.0, 16.0, RED)));
+
+    // Drawn unmasked.
+    let frame = render(&mut state, &mut scene);
+    assert_eq!(pixel(&frame, 32, 12, 12), [255, 0, 0, 255]);
+}
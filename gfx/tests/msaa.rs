@@ -0,0 +1,54 @@
+mod common;
+
+use common::Snapshot;
+use gfx::{
+    config::{pick_sample_count, RendererConfig},
+    scene::Scene,
+    state::State,
+    ui::Element,
+};
+use wgpu::TextureFormatFeatureFlags as Flags;
+
+fn diagonal() -> Element {
+    Element::new()
+        .with_color([1.0, 1.0, 1.0, 1.0])
+        .with_shape(vec![[0.0, 0.0, 0.0], [0.0, 64.0, 0.0], [64.0, 64.0, 0.0]])
+}
+
+// Pixels that are neither fully covered nor empty.
+fn partial_pixels(frame: &[u8]) -> usize {
+    frame.chunks(4).filter(|pixel| pixel[3] > 8 && pixel[3] < 247).count()
+}
+
+#[test]
+fn multisampling_smooths_edges() {
+    let aliased = Snapshot::new("msaa_off").render(vec![diagonal()]);
+    assert_eq!(partial_pixels(&aliased), 0);
+
+    let smooth = Snapshot::new("msaa_4x").with_sample_count(4).render(vec![diagonal()]);
+    assert!(partial_pixels(&smooth) >= 32);
+}
+
+#[test]
+fn sample_count_survives_resize() {
+    let config = RendererConfig::default().with_sample_count(4);
+    let mut state = pollster::block_on(State::new_headless_with_config(32, 32, &config)).unwrap();
+    assert_eq!(state.sample_count(), 4);
+
+    state.resize(winit::dpi::PhysicalSize::new(48, 40));
+    let mut scene = Scene::new();
+    scene.add(diagonal());
+    state.render(&mut scene).unwrap();
+    assert_eq!(state.read_frame().unwrap().len(), 48 * 40 * 4);
+}
+
+#[test]
+fn unsupported_counts_fall_back() {
+    let x4 = Flags::MULTISAMPLE_X4 | Flags::MULTISAMPLE_RESOLVE;
+    assert_eq!(pick_sample_count(1, x4), 1);
+    assert_eq!(pick_sample_count(4, x4), 4);
+    assert_eq!(pick_sample_count(8, x4), 4);
+    assert_eq!(pick_sample_count(2, x4), 1);
+    assert_eq!(pick_sample_count(8, x4 | Flags::MULTISAMPLE_X8), 8);
+    assert_eq!(pick_sample_count(0, x4), 1);
+}
@@ -0,0 +1,145 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gfx::{
+    layout::{Direction, Size},
+    pointer::Pointer,
+    scene::Scene,
+    shapes::Rect,
+    stroke::Stroke,
+    ui::Element,
+};
+use winit::event::MouseButton;
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+fn logger(log: &Log, message: &str) -> impl FnMut(&gfx::pointer::PointerEvent) + 'static {
+    let (log, message) = (log.clone(), message.to_string());
+    move |_| log.borrow_mut().push(message.clone())
+}
+
+fn hover_logger(log: &Log, name: &str) -> impl FnMut(bool) + 'static {
+    let (log, name) = (log.clone(), name.to_string());
+    move |hovered| log.borrow_mut().push(format!("{name} {}", if hovered { "enter" } else { "leave" }))
+}
+
+fn take(log: &Log) -> Vec<String> {
+    std::mem::take(&mut *log.borrow_mut())
+}
+
+#[test]
+fn hit_testing_follows_the_shape() {
+    let mut triangle = Element::new().with_shape(vec![[0.0, 0.0, 0.0], [0.0, 10.0, 0.0], [10.0, 10.0, 0.0]]);
+    triangle.compute_layout([100.0, 100.0]);
+    assert!(triangle.hit_test([2.0, 8.0]));
+    // Inside the bounding box, outside the triangle.
+    assert!(!triangle.hit_test([8.0, 2.0]));
+
+    let mut ring = Element::new().with_circle([10.0, 10.0], 8.0, 0.01).with_stroke(Stroke::new(2.0));
+    ring.compute_layout([100.0, 100.0]);
+    assert!(ring.hit_test([18.0, 10.0]));
+    assert!(!ring.hit_test([10.0, 10.0]));
+
+    let mut background = Element::new().with_size(Size::Px(20.0), Size::Px(10.0));
+    background.compute_layout([100.0, 100.0]);
+    assert!(background.hit_test([19.0, 9.0]));
+    assert!(!background.hit_test([21.0, 9.0]));
+}
+
+#[test]
+fn replacing_the_shape_sets_whether_it_is_closed() {
+    let corner = vec![[0.0, 0.0, 0.0], [0.0, 10.0, 0.0], [10.0, 10.0, 0.0]];
+    let mut line = Element::new().with_polyline(corner.clone()).with_stroke(Stroke::new(2.0));
+    line.compute_layout([100.0, 100.0]);
+    // On the segment that only a closed outline has.
+    assert!(!line.hit_test([5.0, 5.0]));
+
+    line.set_shape(corner.clone());
+    line.compute_layout([100.0, 100.0]);
+    assert!(line.hit_test([5.0, 5.0]));
+
+    line.set_polyline(corner);
+    line.compute_layout([100.0, 100.0]);
+    assert!(!line.hit_test([5.0, 5.0]));
+}
+
+#[test]
+fn topmost_element_wins() {
+    let mut scene = Scene::new();
+    let back = scene.add(Element::new().with_rect(Rect::new(0.0, 0.0, 20.0, 20.0)));
+    let front = scene.add(Element::new().with_rect(Rect::new(10.0, 10.0, 20.0, 20.0)));
+
+    assert_eq!(scene.hit_test([5.0, 5.0]), Some(back));
+    assert_eq!(scene.hit_test([15.0, 15.0]), Some(front));
+    assert_eq!(scene.hit_test([50.0, 50.0]), None);
+}
+
+#[test]
+fn hover_press_release_and_click() {
+    let log: Log = Rc::default();
+    let mut button = Element::new()
+        .with_size(Size::Px(40.0), Size::Px(20.0))
+        .on_hover(hover_logger(&log, "button"))
+        .on_press(logger(&log, "press"))
+        .on_release(logger(&log, "release"))
+        .on_click(logger(&log, "click"));
+    button.compute_layout([100.0, 100.0]);
+
+    let mut scene = Scene::new();
+    scene.add(button);
+    let mut pointer = Pointer::new();
+
+    pointer.moved(&mut scene, [10.0, 10.0]);
+    pointer.moved(&mut scene, [12.0, 10.0]);
+    assert_eq!(take(&log), ["button enter"]);
+
+    pointer.button(&mut scene, MouseButton::Left, true);
+    pointer.button(&mut scene, MouseButton::Left, false);
+    assert_eq!(take(&log), ["press", "release", "click"]);
+
+    // Releasing somewhere else is not a click.
+    pointer.button(&mut scene, MouseButton::Left, true);
+    pointer.moved(&mut scene, [60.0, 10.0]);
+    pointer.button(&mut scene, MouseButton::Left, false);
+    assert_eq!(take(&log), ["press", "button leave"]);
+}
+
+#[test]
+fn events_bubble_to_the_nearest_handler() {
+    let log: Log = Rc::default();
+    let mut panel = Element::new()
+        .with_direction(Direction::Row)
+        .on_hover(hover_logger(&log, "panel"))
+        .on_click(logger(&log, "panel click"))
+        .on_scroll(logger(&log, "panel scroll"))
+        .with_children([
+            Element::new()
+                .with_size(Size::Px(20.0), Size::Px(20.0))
+                .on_hover(hover_logger(&log, "a"))
+                .on_click(logger(&log, "a click")),
+            Element::new().with_size(Size::Px(20.0), Size::Px(20.0)).on_hover(hover_logger(&log, "b")),
+        ]);
+    panel.compute_layout([100.0, 100.0]);
+
+    let mut scene = Scene::new();
+    scene.add(panel);
+    let mut pointer = Pointer::new();
+
+    pointer.moved(&mut scene, [5.0, 5.0]);
+    assert_eq!(take(&log), ["panel enter", "a enter"]);
+    pointer.moved(&mut scene, [25.0, 5.0]);
+    assert_eq!(take(&log), ["a leave", "b enter"]);
+
+    // `b` has no click handler of its own.
+    pointer.button(&mut scene, MouseButton::Left, true);
+    pointer.button(&mut scene, MouseButton::Left, false);
+    pointer.scroll(&mut scene, [0.0, -20.0]);
+    assert_eq!(take(&log), ["panel click", "panel scroll"]);
+
+    pointer.moved(&mut scene, [5.0, 5.0]);
+    pointer.button(&mut scene, MouseButton::Left, true);
+    pointer.button(&mut scene, MouseButton::Left, false);
+    assert_eq!(take(&log), ["b leave", "a enter", "a click"]);
+
+    pointer.left(&mut scene);
+    assert_eq!(take(&log), ["a leave", "panel leave"]);
+}
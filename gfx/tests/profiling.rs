@@ -0,0 +1,96 @@
+mod common;
+
+use common::{headless, pixel};
+use gfx::{
+    config::RendererConfig,
+    profiler::{Summary, STATS_FRAMES},
+    scene::Scene,
+    shapes::Rect,
+    state::State,
+    ui::Element,
+};
+
+fn assert_ordered(summary: Summary) {
+    assert!(summary.min <= summary.mean && summary.mean <= summary.max, "{summary:?}");
+    assert!(summary.min <= summary.last && summary.last <= summary.max, "{summary:?}");
+}
+
+#[test]
+fn cpu_timings_cover_the_last_frames() {
+    let mut state = headless(16, 16);
+    let mut scene = Scene::new();
+    scene.add(Element::new().with_color([1.0, 0.0, 0.0, 1.0]).with_rect(Rect::new(0.0, 0.0, 8.0, 8.0)));
+
+    let timings = state.frame_timings();
+    assert_eq!(timings.frames, 0);
+    assert_eq!(timings.cpu, None);
+
+    for _ in 0..5 {
+        state.render(&mut scene).unwrap();
+    }
+    let timings = state.frame_timings();
+    assert_eq!(timings.frames, 5);
+    assert_ordered(timings.cpu.unwrap());
+    assert_ordered(timings.interval.unwrap());
+
+    for _ in 0..STATS_FRAMES {
+        state.render(&mut scene).unwrap();
+    }
+    assert_eq!(state.frame_timings().frames, STATS_FRAMES);
+}
+
+#[test]
+fn gpu_timings_are_reported_per_pass_when_supported() {
+    let mut state = headless(16, 16);
+    let mut scene = Scene::new();
+    for _ in 0..3 {
+        state.render(&mut scene).unwrap();
+    }
+    state.flush_timings();
+
+    let timings = state.frame_timings();
+    if !state.gpu_timing_supported() {
+        assert_eq!(timings.gpu, None);
+        assert!(timings.passes.is_empty());
+        return;
+    }
+    assert!(timings.gpu_frames > 0);
+    assert_ordered(timings.gpu.unwrap());
+    let labels: Vec<&str> = timings.passes.iter().map(|pass| pass.label).collect();
+    assert_eq!(labels, ["scene"]);
+
+    state.set_profiler_overlay(true);
+    state.render(&mut scene).unwrap();
+    state.flush_timings();
+    let labels: Vec<&str> = state.frame_timings().passes.iter().map(|pass| pass.label).collect();
+    assert_eq!(labels, ["scene", "overlay"]);
+}
+
+#[test]
+fn the_overlay_draws_over_the_top_left_corner() {
+    let mut state = headless(300, 120);
+    let mut scene = Scene::new();
+    state.render(&mut scene).unwrap();
+    assert_eq!(pixel(&state.read_frame().unwrap(), 300, 10, 10), [0; 4]);
+
+    state.set_profiler_overlay(true);
+    assert!(state.profiler_overlay());
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_ne!(pixel(&frame, 300, 10, 10)[3], 0);
+    assert_eq!(pixel(&frame, 300, 290, 110), [0; 4]);
+    // The overlay is not part of the scene's statistics.
+    assert_eq!(state.draw_stats().draw_calls, 0);
+
+    state.set_profiler_overlay(false);
+    state.render(&mut scene).unwrap();
+    assert_eq!(pixel(&state.read_frame().unwrap(), 300, 10, 10), [0; 4]);
+}
+
+#[test]
+fn the_overlay_is_configurable() {
+    assert!(!RendererConfig::default().profiler_overlay);
+    let config = RendererConfig::default().with_profiler_overlay(true);
+    let state = pollster::block_on(State::new_headless_with_config(16, 16, &config)).unwrap();
+    assert!(state.profiler_overlay());
+}
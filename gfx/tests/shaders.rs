@@ -0,0 +1,108 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use gfx::{
+    config::RendererConfig,
+    scene::Scene,
+    shaders::{self, ShaderKind},
+    state::State,
+    ui::Element,
+};
+
+const RETURNED: &str = "return vec4<f32>(in.color.rgb * in.color.a, in.color.a);";
+
+// The color shader, painting everything green instead.
+fn green_shader() -> String {
+    let source = ShaderKind::Color.builtin();
+    assert!(source.contains(RETURNED));
+    source.replace(RETURNED, "return vec4<f32>(0.0, 1.0, 0.0, 1.0);")
+}
+
+fn red_square() -> Element {
+    Element::new()
+        .with_color([1.0, 0.0, 0.0, 1.0])
+        .with_shape(vec![[0.0, 0.0, 0.0], [0.0, 16.0, 0.0], [16.0, 16.0, 0.0], [16.0, 0.0, 0.0]])
+}
+
+fn first_pixel(state: &mut State) -> [u8; 4] {
+    let mut scene = Scene::new();
+    scene.add(red_square());
+    state.render(&mut scene).unwrap();
+    state.read_frame().unwrap()[..4].try_into().unwrap()
+}
+
+fn shader_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("shaders").join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn builtin_shaders_validate() {
+    for kind in ShaderKind::ALL {
+        shaders::validate(kind.builtin()).unwrap_or_else(|err| panic!("{}: {err:#}", kind.file_name()));
+    }
+}
+
+#[test]
+fn reloading_replaces_the_pipeline() {
+    let mut state = pollster::block_on(State::new_headless(16, 16)).unwrap();
+    assert_eq!(first_pixel(&mut state), [255, 0, 0, 255]);
+
+    state.reload_shader(ShaderKind::Color, &green_shader()).unwrap();
+    assert_eq!(first_pixel(&mut state), [0, 255, 0, 255]);
+}
+
+#[test]
+fn broken_shaders_keep_the_last_good_pipeline() {
+    let mut state = pollster::block_on(State::new_headless(16, 16)).unwrap();
+    state.reload_shader(ShaderKind::Color, &green_shader()).unwrap();
+
+    let err = state.reload_shader(ShaderKind::Color, "fn fs_main( {").unwrap_err();
+    assert!(!err.to_string().is_empty());
+    assert_eq!(first_pixel(&mut state), [0, 255, 0, 255]);
+
+    // Valid WGSL, but without the entry point the pipeline needs.
+    let renamed = ShaderKind::Color.builtin().replace("fn fs_main", "fn fs_other");
+    shaders::validate(&renamed).unwrap();
+    assert!(state.reload_shader(ShaderKind::Color, &renamed).is_err());
+    assert_eq!(first_pixel(&mut state), [0, 255, 0, 255]);
+}
+
+#[test]
+fn shaders_load_from_the_shader_dir() {
+    let dir = shader_dir("load");
+    std::fs::write(dir.join("shader.wgsl"), green_shader()).unwrap();
+    // Broken files fall back to the built-in shader.
+    std::fs::write(dir.join("text.wgsl"), "not wgsl").unwrap();
+
+    let config = RendererConfig::default().with_shader_dir(&dir);
+    let mut state = pollster::block_on(State::new_headless_with_config(16, 16, &config)).unwrap();
+    assert_eq!(first_pixel(&mut state), [0, 255, 0, 255]);
+}
+
+#[test]
+fn missing_shader_dir_is_an_error() {
+    let dir = shader_dir("missing").join("nowhere");
+    let config = RendererConfig::default().with_shader_dir(dir);
+    assert!(pollster::block_on(State::new_headless_with_config(16, 16, &config)).is_err());
+}
+
+#[test]
+fn saved_shaders_are_picked_up_by_the_next_frames() {
+    let dir = shader_dir("watch");
+    let config = RendererConfig::default().with_shader_dir(&dir);
+    let mut state = pollster::block_on(State::new_headless_with_config(16, 16, &config)).unwrap();
+    assert_eq!(first_pixel(&mut state), [255, 0, 0, 255]);
+
+    std::fs::write(dir.join("shader.wgsl"), green_shader()).unwrap();
+    // File events arrive asynchronously, so give the watcher a moment.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while first_pixel(&mut state) != [0, 255, 0, 255] {
+        assert!(Instant::now() < deadline, "the saved shader was never loaded");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,69 @@
+use std::f32::consts::PI;
+
+use gfx::{shapes::Rect, ui::{Element, Mesh}};
+
+fn area(mesh: &Mesh) -> f32 {
+    mesh.indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize].position);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])) / 2.0
+        })
+        .sum()
+}
+
+fn max_distance_from(mesh: &Mesh, center: [f32; 2]) -> f32 {
+    mesh.vertices
+        .iter()
+        .map(|v| ((v.position[0] - center[0]).powi(2) + (v.position[1] - center[1]).powi(2)).sqrt())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn rect_covers_its_area() {
+    let mesh = Element::new().with_rect(Rect::new(1.0, 2.0, 3.0, 4.0)).build();
+    assert_eq!(mesh.indices.len(), 6);
+    assert!((area(&mesh) - 12.0).abs() < 1e-5);
+}
+
+#[test]
+fn circle_stays_within_tolerance() {
+    let tolerance = 0.01;
+    let mesh = Element::new().with_circle([5.0, 5.0], 2.0, tolerance).build();
+
+    let exact = PI * 4.0;
+    // Every chord cuts off at most `tolerance` of depth along the perimeter.
+    assert!(area(&mesh) <= exact);
+    assert!(exact - area(&mesh) <= tolerance * 2.0 * PI * 2.0);
+    assert!(max_distance_from(&mesh, [5.0, 5.0]) <= 2.0 + 1e-5);
+
+    let coarse = Element::new().with_circle([5.0, 5.0], 2.0, 0.5).build();
+    assert!(coarse.vertices.len() < mesh.vertices.len());
+}
+
+#[test]
+fn rounded_rect_clamps_oversized_radii() {
+    // Radii this large turn a square into a circle.
+    let mesh = Element::new()
+        .with_rounded_rect(Rect::new(0.0, 0.0, 2.0, 2.0), [5.0; 4], 0.001)
+        .build();
+    assert!((area(&mesh) - PI).abs() < 0.01);
+
+    let square = Element::new()
+        .with_rounded_rect(Rect::new(0.0, 0.0, 2.0, 2.0), [0.0; 4], 0.001)
+        .build();
+    assert!((area(&square) - 4.0).abs() < 1e-5);
+}
+
+#[test]
+fn ellipse_and_pie() {
+    let ellipse = Element::new().with_ellipse([0.0, 0.0], [3.0, 1.0], 0.001).build();
+    assert!((area(&ellipse) - PI * 3.0).abs() < 0.02);
+
+    let quarter = Element::new().with_arc([0.0, 0.0], 1.0, 0.0, PI / 2.0, 0.001).build();
+    assert!((area(&quarter) - PI / 4.0).abs() < 0.01);
+
+    // A pie past half a turn is concave and still triangulates fully.
+    let three_quarters = Element::new().with_arc([0.0, 0.0], 1.0, 0.0, 1.5 * PI, 0.001).build();
+    assert!((area(&three_quarters) - 0.75 * PI).abs() < 0.01);
+}
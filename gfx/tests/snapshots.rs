@@ -0,0 +1,137 @@
+mod common;
+
+use common::Snapshot;
+use gfx::{
+    layout::{Align, Direction, Edges, Size},
+    shapes::Rect,
+    stroke::{LineCap, LineJoin, Stroke},
+    ui::Element,
+};
+
+#[test]
+fn empty_scene_is_transparent() {
+    let frame = Snapshot::new("empty").render(Vec::new());
+    assert!(frame.iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn single_triangle() {
+    let triangle = Element::new()
+        .with_color([1.0, 0.0, 0.0, 1.0])
+        .with_shape(vec![[16.0, 16.0, 0.0], [16.0, 48.0, 0.0], [48.0, 48.0, 0.0]]);
+
+    Snapshot::new("single_triangle").assert_matches(vec![triangle]);
+}
+
+#[test]
+fn overlapping_triangles() {
+    let back = Element::new()
+        .with_color([0.0, 0.0, 1.0, 1.0])
+        .with_shape(vec![[8.0, 8.0, 0.0], [8.0, 56.0, 0.0], [88.0, 56.0, 0.0]]);
+    let front = Element::new()
+        .with_color([0.0, 1.0, 0.0, 1.0])
+        .with_shape(vec![[88.0, 8.0, 0.0], [8.0, 8.0, 0.0], [88.0, 56.0, 0.0]]);
+
+    Snapshot::new("overlapping_triangles")
+        .with_size(96, 64)
+        .assert_matches(vec![back, front]);
+}
+
+#[test]
+fn concave_polygon() {
+    let arrow = Element::new()
+        .with_color([1.0, 1.0, 0.0, 1.0])
+        .with_shape(vec![
+            [6.0, 22.0, 0.0],
+            [38.0, 22.0, 0.0],
+            [38.0, 6.0, 0.0],
+            [58.0, 32.0, 0.0],
+            [38.0, 58.0, 0.0],
+            [38.0, 42.0, 0.0],
+            [6.0, 42.0, 0.0],
+        ]);
+
+    Snapshot::new("concave_polygon").assert_matches(vec![arrow]);
+}
+
+#[test]
+fn shape_primitives() {
+    let elements = vec![
+        Element::new()
+            .with_color([1.0, 1.0, 1.0, 1.0])
+            .with_rounded_rect(Rect::new(6.0, 6.0, 116.0, 116.0), [0.0, 12.0, 24.0, 36.0], 0.25),
+        Element::new()
+            .with_color([1.0, 0.0, 1.0, 1.0])
+            .with_ellipse([38.0, 38.0], [22.0, 13.0], 0.25),
+        Element::new()
+            .with_color([0.0, 1.0, 1.0, 1.0])
+            .with_arc([90.0, 90.0], 22.0, 0.5, 5.0, 0.25),
+    ];
+
+    Snapshot::new("shape_primitives").with_size(128, 128).assert_matches(elements);
+}
+
+#[test]
+fn strokes() {
+    let elements = vec![
+        Element::new()
+            .with_color([1.0, 1.0, 1.0, 1.0])
+            .with_rounded_rect(Rect::new(13.0, 13.0, 102.0, 102.0), [20.0; 4], 0.25)
+            .with_stroke(Stroke::new(5.0)),
+        Element::new()
+            .with_color([1.0, 0.5, 0.0, 1.0])
+            .with_polyline(vec![[26.0, 96.0, 0.0], [64.0, 32.0, 0.0], [102.0, 96.0, 0.0]])
+            .with_stroke(
+                Stroke::new(6.0)
+                    .with_join(LineJoin::Round)
+                    .with_cap(LineCap::Round)
+                    .with_tolerance(0.25),
+            ),
+        Element::new()
+            .with_color([0.0, 0.5, 1.0, 1.0])
+            .with_polyline(vec![[26.0, 64.0, 0.0], [102.0, 64.0, 0.0]])
+            .with_stroke(Stroke::new(3.0).with_dash(vec![6.0, 3.0], 0.0)),
+    ];
+
+    Snapshot::new("strokes").with_size(128, 128).assert_matches(elements);
+}
+
+#[test]
+fn coordinates_are_logical_pixels() {
+    let square = || {
+        vec![Element::new()
+            .with_color([1.0, 1.0, 1.0, 1.0])
+            .with_rect(Rect::new(8.0, 8.0, 16.0, 16.0))]
+    };
+
+    // At a scale factor of 2, the same square covers four times the pixels.
+    let at_1x = Snapshot::new("scale_1x").render(square());
+    let at_2x = Snapshot::new("scale_2x").with_scale_factor(2.0).render(square());
+    let covered = |frame: &[u8]| frame.chunks_exact(4).filter(|px| px[3] == 255).count();
+    assert_eq!(covered(&at_1x), 16 * 16);
+    assert_eq!(covered(&at_2x), 32 * 32);
+}
+
+#[test]
+fn layout_panel() {
+    let panel = Element::new()
+        .with_color([0.2, 0.2, 0.2, 1.0])
+        .with_size(Size::Fill(1.0), Size::Fill(1.0))
+        .with_padding(Edges::all(8.0))
+        .with_gap(4.0)
+        .with_align(Align::Stretch)
+        .with_children([
+            Element::new().with_color([1.0, 0.0, 0.0, 1.0]).with_size(Size::Auto, Size::Px(12.0)),
+            Element::new()
+                .with_direction(Direction::Row)
+                .with_size(Size::Auto, Size::Fill(1.0))
+                .with_gap(4.0)
+                .with_align(Align::Stretch)
+                .with_children([
+                    Element::new().with_color([0.0, 1.0, 0.0, 1.0]).with_size(Size::Px(16.0), Size::Auto),
+                    Element::new().with_color([0.0, 0.0, 1.0, 1.0]).with_size(Size::Fill(1.0), Size::Auto),
+                ]),
+        ]);
+
+    Snapshot::new("layout_panel").assert_matches(vec![panel]);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::{assert_close, pixel, Snapshot};
+use gfx::{
+    stroke::{LineCap, LineJoin, Stroke},
+    ui::{Element, Mesh},
+};
+
+fn area(mesh: &Mesh) -> f32 {
+    let mut total = 0.0;
+    for tri in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize].position);
+        let area = ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])) / 2.0;
+        assert!(area > 0.0, "triangles must be counter-clockwise");
+        total += area;
+    }
+    total
+}
+
+fn bounds(mesh: &Mesh) -> [f32; 4] {
+    mesh.vertices.iter().fold(
+        [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
+        |[x0, y0, x1, y1], v| {
+            [x0.min(v.position[0]), y0.min(v.position[1]), x1.max(v.position[0]), y1.max(v.position[1])]
+        },
+    )
+}
+
+fn line(stroke: Stroke) -> Mesh {
+    Element::new()
+        .with_polyline(vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]])
+        .with_stroke(stroke)
+        .build()
+}
+
+#[test]
+fn caps_extend_the_line() {
+    let butt = line(Stroke::new(2.0));
+    assert!((area(&butt) - 20.0).abs() < 1e-4);
+    assert_eq!(bounds(&butt), [0.0, -1.0, 10.0, 1.0]);
+
+    let square = line(Stroke::new(2.0).with_cap(LineCap::Square));
+    assert!((area(&square) - 24.0).abs() < 1e-4);
+    assert_eq!(bounds(&square), [-1.0, -1.0, 11.0, 1.0]);
+
+    let round = line(Stroke::new(2.0).with_cap(LineCap::Round).with_tolerance(0.001));
+    assert!((area(&round) - (20.0 + std::f32::consts::PI)).abs() < 0.01);
+}
+
+fn corner(join: LineJoin) -> Mesh {
+    Element::new()
+        .with_polyline(vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [10.0, 10.0, 0.0]])
+        .with_stroke(Stroke::new(2.0).with_join(join).with_tolerance(0.001))
+        .build()
+}
+
+#[test]
+fn joins_on_a_right_angle() {
+    // The miter fills the outer corner up to (11, -1).
+    assert_eq!(bounds(&corner(LineJoin::Miter)), [0.0, -1.0, 11.0, 10.0]);
+    let bevel = corner(LineJoin::Bevel);
+    let round = corner(LineJoin::Round);
+    assert!(area(&bevel) < area(&round));
+    assert!(area(&round) < area(&corner(LineJoin::Miter)));
+
+    // A miter limit of 1 turns every miter into a bevel.
+    let limited = Element::new()
+        .with_polyline(vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [10.0, 10.0, 0.0]])
+        .with_stroke(Stroke::new(2.0).with_miter_limit(1.0))
+        .build();
+    assert!((area(&limited) - area(&bevel)).abs() < 1e-4);
+}
+
+#[test]
+fn dashes_cover_only_the_on_lengths() {
+    let dashed = line(Stroke::new(2.0).with_dash(vec![2.0, 1.0], 0.0));
+    // 10 units of pattern: on [0,2], [3,5], [6,8], [9,10].
+    assert!((area(&dashed) - 14.0).abs() < 1e-4);
+
+    let offset = line(Stroke::new(2.0).with_dash(vec![2.0, 1.0], 1.0));
+    // on [0,1], [2,4], [5,7], [8,10].
+    assert!((area(&offset) - 14.0).abs() < 1e-4);
+    assert_eq!(bounds(&offset)[0], 0.0);
+}
+
+#[test]
+fn closed_outline_has_no_caps() {
+    let square = Element::new()
+        .with_shape(vec![[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [4.0, 4.0, 0.0], [0.0, 4.0, 0.0]])
+        .with_stroke(Stroke::new(2.0).with_cap(LineCap::Square))
+        .build();
+    assert_eq!(bounds(&square), [-1.0, -1.0, 5.0, 5.0]);
+}
+
+#[test]
+fn corner_pieces_do_not_overlap() {
+    // Both 20-unit segments, less the unit square they share, plus the
+    // outer corner.
+    assert!((area(&corner(LineJoin::Bevel)) - 39.5).abs() < 1e-4);
+    assert!((area(&corner(LineJoin::Miter)) - 40.0).abs() < 1e-4);
+    assert!((area(&corner(LineJoin::Round)) - (39.0 + std::f32::consts::FRAC_PI_4)).abs() < 0.01);
+}
+
+#[test]
+fn translucent_strokes_blend_once_at_corners() {
+    let polyline = Element::new()
+        .with_color([1.0, 0.0, 0.0, 0.5])
+        .with_polyline(vec![[8.0, 32.0, 0.0], [32.0, 32.0, 0.0], [32.0, 8.0, 0.0]])
+        .with_stroke(Stroke::new(8.0));
+    let frame = Snapshot::new("translucent_stroke").render(vec![polyline]);
+
+    // Along a segment, on the inner and on the outer side of the corner.
+    for (x, y) in [(16, 32), (30, 30), (34, 34)] {
+        assert_close(pixel(&frame, 64, x, y), [188, 0, 0, 128]);
+    }
+}
+
+#[test]
+fn zero_length_dashes_draw_only_their_caps() {
+    // Dots at 0, 4 and 8.
+    let dotted = line(Stroke::new(2.0).with_dash(vec![0.0, 4.0], 0.0).with_cap(LineCap::Round).with_tolerance(0.001));
+    assert!(dotted.vertices.iter().all(|vertex| vertex.position.iter().all(|v| v.is_finite())));
+    assert!((area(&dotted) - 3.0 * std::f32::consts::PI).abs() < 0.03);
+
+    // Butt caps leave nothing to draw.
+    assert!(line(Stroke::new(2.0).with_dash(vec![0.0, 4.0], 0.0)).indices.is_empty());
+}
+
+#[test]
+fn repeated_points_are_skipped() {
+    let mesh = Element::new()
+        .with_polyline(vec![[0.0, 0.0, 0.0], [5.0, 0.0, 0.0], [5.0, 0.0, 0.0], [10.0, 0.0, 0.0]])
+        .with_stroke(Stroke::new(2.0))
+        .build();
+    assert!(mesh.vertices.iter().all(|vertex| vertex.position.iter().all(|v| v.is_finite())));
+    assert!((area(&mesh) - 20.0).abs() < 1e-4);
+}
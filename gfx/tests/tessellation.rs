@@ -0,0 +1,42 @@
+use gfx::ui::{Element, Mesh};
+
+fn triangle_areas(mesh: &Mesh) -> Vec<f32> {
+    mesh.indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize].position);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])) / 2.0
+        })
+        .collect()
+}
+
+#[test]
+fn concave_polygon_covers_its_area() {
+    // An "L" shape, wound clockwise, with an area of 3.
+    let mesh = Element::new()
+        .with_shape(vec![
+            [0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [1.0, 2.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [2.0, 0.0, 0.0],
+        ])
+        .build();
+
+    let areas = triangle_areas(&mesh);
+    assert_eq!(areas.len(), 4);
+    assert!(areas.iter().all(|&area| area > 0.0), "triangles must be counter-clockwise");
+    assert!((areas.iter().sum::<f32>() - 3.0).abs() < 1e-5);
+}
+
+#[test]
+fn closing_point_and_short_outlines() {
+    let closed = Element::new()
+        .with_shape(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]])
+        .build();
+    assert_eq!(closed.indices.len(), 3);
+
+    let line = Element::new().with_shape(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]).build();
+    assert!(line.indices.is_empty());
+}
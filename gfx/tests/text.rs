@@ -0,0 +1,69 @@
+use gfx::{
+    layout::Size,
+    text::{Font, Text},
+    ui::{Element, Material},
+};
+
+// DejaVu Sans cut down to Latin-1, see `fonts/LICENSE`.
+const FONT: &[u8] = include_bytes!("fonts/DejaVuSans-Latin1.ttf");
+
+fn font() -> Font {
+    Font::from_bytes(FONT).unwrap()
+}
+
+#[test]
+fn measure_grows_with_content() {
+    let font = font();
+    let short = Text::new("Hi", &font, 16.0).measure();
+    let long = Text::new("Hi there", &font, 16.0).measure();
+
+    assert!(short[0] > 0.0 && short[1] > 0.0);
+    assert!(long[0] > short[0]);
+    assert_eq!(long[1], short[1]);
+}
+
+#[test]
+fn auto_sized_elements_fit_their_text() {
+    let font = font();
+    let text = Text::new("Hello", &font, 20.0);
+    let size = text.measure();
+    let mut element = Element::new().with_text(text);
+    element.compute_layout([200.0, 200.0]);
+
+    assert_eq!(element.rect().width, size[0]);
+    assert_eq!(element.rect().height, size[1]);
+
+    let mut fixed = Element::new().with_size(Size::Px(4.0), Size::Px(4.0)).with_text(Text::new("Hello", &font, 20.0));
+    fixed.compute_layout([200.0, 200.0]);
+    assert_eq!(fixed.rect().width, 4.0);
+}
+
+#[test]
+fn glyphs_become_text_quads_on_top_of_the_background() {
+    let font = font();
+    let mut element = Element::new()
+        .with_color([1.0, 1.0, 1.0, 1.0])
+        .with_text(Text::new("A B", &font, 16.0));
+    element.compute_layout([100.0, 100.0]);
+    let mesh = element.build();
+
+    // The background rect, then one quad for each glyph with coverage; the
+    // space has none.
+    assert_eq!(mesh.draws.len(), 2);
+    assert_eq!(mesh.draws[0].material, Material::Color);
+    assert_eq!(mesh.draws[1].material, Material::Text);
+    assert_eq!(mesh.draws[1].indices.len(), 2 * 6);
+    assert_eq!(mesh.draws[1].indices.end as usize, mesh.indices.len());
+
+    let rect = element.rect();
+    for index in &mesh.indices[mesh.draws[1].indices.start as usize..] {
+        let [x, y, _] = mesh.vertices[*index as usize].position;
+        assert!(x >= rect.x - 1.0 && x <= rect.x + rect.width + 1.0);
+        assert!(y >= rect.y - 1.0 && y <= rect.y + rect.height + 1.0);
+    }
+}
+
+#[test]
+fn invalid_fonts_are_rejected() {
+    assert!(Font::from_bytes(b"not a font").is_err());
+}
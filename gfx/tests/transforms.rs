@@ -0,0 +1,100 @@
+mod common;
+
+use std::f32::consts::FRAC_PI_2;
+
+use common::{headless, pixel, square, RED, WHITE};
+use gfx::{
+    layout::{Direction, Size},
+    scene::Scene,
+    transform::Transform,
+    ui::Element,
+};
+
+fn assert_near(actual: [f32; 2], expected: [f32; 2]) {
+    assert!(
+        (actual[0] - expected[0]).abs() < 1e-4 && (actual[1] - expected[1]).abs() < 1e-4,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn transforms_compose_in_order() {
+    let t = Transform::scale(2.0, 3.0).then(Transform::translate(10.0, 20.0));
+    assert_near(t.apply([1.0, 1.0]), [12.0, 23.0]);
+
+    // +x turns towards +y.
+    assert_near(Transform::rotate(FRAC_PI_2).apply([1.0, 0.0]), [0.0, 1.0]);
+    assert_near(Transform::rotate(FRAC_PI_2).around([5.0, 5.0]).apply([5.0, 5.0]), [5.0, 5.0]);
+    assert_near(Transform::skew(FRAC_PI_2 / 2.0, 0.0).apply([0.0, 2.0]), [2.0, 2.0]);
+
+    let t = Transform::rotate(0.3).then(Transform::scale(2.0, 0.5)).then(Transform::translate(-4.0, 7.0));
+    let inverse = t.inverse().unwrap();
+    assert_near(inverse.apply(t.apply([3.0, -2.0])), [3.0, -2.0]);
+    assert!(Transform::scale(0.0, 1.0).inverse().is_none());
+}
+
+#[test]
+fn children_inherit_the_transforms_of_their_parents() {
+    let mut parent = Element::new()
+        .with_direction(Direction::Stack)
+        .with_size(Size::Px(40.0), Size::Px(40.0))
+        .with_transform(Transform::translate(10.0, 0.0))
+        .with_child(square(0.0, 0.0, 10.0, WHITE).with_transform(Transform::scale(2.0, 2.0)));
+    parent.compute_layout([100.0, 100.0]);
+
+    let transforms = parent.world_transforms();
+    assert_eq!(transforms.len(), 2);
+    assert_near(transforms[0].apply([0.0, 0.0]), [10.0, 0.0]);
+    assert_near(transforms[1].apply([5.0, 5.0]), [20.0, 10.0]);
+
+    // One draw per element, so each gets its own transform.
+    let mesh = parent.build();
+    let nodes: Vec<u32> = mesh.draws.iter().map(|draw| draw.node).collect();
+    assert_eq!(nodes, [1]);
+    let mesh = Element::new()
+        .with_color([1.0; 4])
+        .with_size(Size::Px(10.0), Size::Px(10.0))
+        .with_child(square(0.0, 0.0, 5.0, WHITE))
+        .build();
+    let nodes: Vec<u32> = mesh.draws.iter().map(|draw| draw.node).collect();
+    assert_eq!(nodes, [0, 1]);
+}
+
+#[test]
+fn transforms_move_what_is_drawn() {
+    let mut state = headless(32, 32);
+    let mut scene = Scene::new();
+    let id = scene.add(square(0.0, 0.0, 8.0, RED));
+
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 4, 4), [255, 0, 0, 255]);
+
+    assert!(scene.set_transform(id, Transform::translate(16.0, 16.0)));
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 4, 4), [0, 0, 0, 0]);
+    assert_eq!(pixel(&frame, 32, 20, 20), [255, 0, 0, 255]);
+
+    // Scaled around the square's center, it covers the whole target.
+    scene.set_transform(id, Transform::scale(4.0, 4.0).around([4.0, 4.0]));
+    state.render(&mut scene).unwrap();
+    let frame = state.read_frame().unwrap();
+    assert_eq!(pixel(&frame, 32, 19, 1), [255, 0, 0, 255]);
+}
+
+#[test]
+fn hit_testing_follows_transforms() {
+    let mut element = square(0.0, 0.0, 10.0, WHITE).with_transform(Transform::rotate(FRAC_PI_2).around([5.0, 5.0]));
+    element.compute_layout([100.0, 100.0]);
+    let mut scene = Scene::new();
+    let id = scene.add(element);
+    assert_eq!(scene.hit_test([2.0, 2.0]), Some(id));
+
+    scene.set_transform(id, Transform::translate(50.0, 0.0));
+    assert_eq!(scene.hit_test([2.0, 2.0]), None);
+    assert_eq!(scene.hit_test([52.0, 2.0]), Some(id));
+
+    scene.set_transform(id, Transform::scale(0.0, 0.0));
+    assert_eq!(scene.hit_test([0.0, 0.0]), None);
+}